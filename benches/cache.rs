@@ -1,3 +1,4 @@
+#![cfg(feature = "nightly")]
 #![feature(test)]
 
 extern crate cascara;
@@ -10,18 +11,18 @@ use cascara::Cache;
 #[bench]
 fn insert(b: &mut Bencher) {
     b.iter(|| {
-        let mut cache = Cache::new(100, 200);
+        let mut cache = Cache::new(100);
         for i in 0..1000 {
-            cache.insert(i, i);
+            let _ = cache.insert(i, i);
         }
     })
 }
 
 #[bench]
 fn get(b: &mut Bencher) {
-    let mut cache = Cache::new(100, 200);
+    let mut cache = Cache::new(100);
     for i in 0..1000 {
-        cache.insert(i, i);
+        let _ = cache.insert(i, i);
     }
     b.iter(|| {
         for i in 0..1000 {
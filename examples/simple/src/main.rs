@@ -1,14 +1,14 @@
 extern crate cascara;
 
-use cascara::{Cache, OnEvict};
+use cascara::{Cache, OnEvict, RemovalCause};
 use std::time::Duration;
 
 #[derive(Default, Debug)]
 struct Evict {}
 
 impl OnEvict<usize, usize> for Evict {
-    fn evict(&self, k: &usize, v: &usize) {
-        println!("Evict item.  k={}, v={}", k, v);
+    fn evict(&self, k: usize, v: usize, cause: RemovalCause) {
+        println!("Evict item.  k={}, v={}, cause={:?}", k, v, cause);
     }
 }
 
@@ -20,7 +20,8 @@ fn main() {
     cache.insert(1, 1).expect("Item is not inserted");
     assert_eq!(cache.get(&1), Some(&1));
     let previous = cache.insert(1, 2).expect("Item is not updated");
-    assert_eq!(previous, Some(1));
+    //replaced value is moved to Evict
+    assert_eq!(previous, None);
     assert_eq!(cache.get(&1), Some(&2));
     cache
         .insert_with_ttl(2, 2, Duration::from_secs(1))
@@ -0,0 +1,147 @@
+use crate::store::SampleItem;
+use std::collections::VecDeque;
+
+///
+/// Decision of cache about admission of incoming item
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdmissionDecision {
+    ///
+    /// Item is admitted. Victims have to be removed to release room for it.
+    ///
+    Admitted { victims: Vec<SampleItem> },
+
+    ///
+    /// Estimate of incoming item is lower than combined estimate of sampled victims.
+    /// Victim is the sampled item with the lowest estimate.
+    ///
+    Rejected {
+        incoming_estimate: i64,
+        victim: Option<SampleItem>,
+        victim_estimate: i64,
+    },
+
+    ///
+    /// Cost of item is bigger than capacity of cache.
+    ///
+    TooCostly { cost: usize, capacity: usize },
+
+    ///
+    /// Eviction of items can not release enough room for cost of item,
+    /// or item needs more victims than are sampled for one insert.
+    ///
+    NoRoom { cost: usize, room: usize },
+}
+
+impl AdmissionDecision {
+    ///
+    /// Returns true if item is admitted
+    ///
+    pub fn is_admitted(&self) -> bool {
+        matches!(self, AdmissionDecision::Admitted { .. })
+    }
+}
+
+///
+/// Sampled admission decision of cache
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdmissionRecord {
+    ///
+    /// Hash of item key
+    ///
+    pub key_hash: u64,
+
+    ///
+    /// Item cost
+    ///
+    pub cost: usize,
+
+    ///
+    /// Decision about item
+    ///
+    pub decision: AdmissionDecision,
+}
+
+///
+/// Bounded log of every n-th admission decision
+///
+#[derive(Clone, Debug)]
+pub struct AdmissionLog {
+    capacity: usize,
+    every: u64,
+    decisions: u64,
+    records: VecDeque<AdmissionRecord>,
+}
+
+impl AdmissionLog {
+    ///
+    /// Create new empty log
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max number of kept records, the oldest records are dropped
+    /// - `every`: every n-th decision is recorded
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` or `every` is 0.
+    ///
+    pub fn new(capacity: usize, every: u64) -> Self {
+        assert_ne!(capacity, 0);
+        assert_ne!(every, 0);
+        Self {
+            capacity,
+            every,
+            decisions: 0,
+            records: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    ///
+    /// Count decision and record it, if it is sampled
+    ///
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn record(&mut self, key_hash: u64, cost: usize, decision: &AdmissionDecision) {
+        self.decisions += 1;
+        //`is_multiple_of` is not available on older toolchains
+        if self.decisions % self.every != 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(AdmissionRecord {
+            key_hash,
+            cost,
+            decision: decision.clone(),
+        });
+    }
+
+    ///
+    /// Returns recorded decisions from the oldest one
+    ///
+    pub fn records(&self) -> Vec<AdmissionRecord> {
+        self.records.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::admission::{AdmissionDecision, AdmissionLog};
+
+    #[test]
+    fn sampled_and_bounded() {
+        let mut log = AdmissionLog::new(2, 2);
+        let decision = AdmissionDecision::Admitted { victims: vec![] };
+        for key_hash in 1..=6 {
+            log.record(key_hash, 1, &decision);
+        }
+        let keys = log
+            .records()
+            .iter()
+            .map(|record| record.key_hash)
+            .collect::<Vec<u64>>();
+        assert_eq!(keys, vec![4, 6]);
+    }
+}
@@ -0,0 +1,394 @@
+use crate::cache::{Cache, OnEvict, UnitWeigher, VoidEvict};
+use crate::error::BuildError;
+use crate::store::{Storage, Store};
+use crate::tiny_lfu::{TinyLFU, TinyLFUCache, TinyLFUConfig};
+use probabilistic_collections::SipHasherBuilder;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+///
+/// Part of cache which is given by user or created with default implementation at build.
+///
+enum Part<T, C> {
+    New(fn(C) -> T),
+    Custom(T),
+}
+
+///
+/// Builder of cache, which validates configuration instead of panic.
+///
+/// # Example
+///
+/// ```
+/// use cascara::{Cache, CacheBuilder};
+/// use std::time::Duration;
+///
+/// let mut cache: Cache<u8, u8> = CacheBuilder::new()
+///     .capacity(100)
+///     .window_size(1000)
+///     .default_ttl(Duration::from_secs(60))
+///     .metrics(true)
+///     .build()
+///     .unwrap();
+/// assert!(cache.insert(1, 1).is_ok());
+/// assert!(cache.metrics().is_some());
+/// ```
+///
+pub struct CacheBuilder<
+    K,
+    V,
+    E = VoidEvict<K, V>,
+    S = Storage<K, V>,
+    A = TinyLFUCache,
+    H = SipHasherBuilder,
+> {
+    capacity: Option<usize>,
+    window_size: Option<usize>,
+    on_evict: Option<E>,
+    hasher_builder: H,
+    store: Part<S, usize>,
+    admission: Part<A, (usize, TinyLFUConfig)>,
+    default_ttl: Duration,
+    metrics: bool,
+    _k: PhantomData<K>,
+    _v: PhantomData<V>,
+}
+
+impl<K, V> CacheBuilder<K, V> {
+    ///
+    /// Create new builder of cache with default parts
+    ///
+    pub fn new() -> Self {
+        Self {
+            capacity: None,
+            window_size: None,
+            on_evict: None,
+            hasher_builder: SipHasherBuilder::from_entropy(),
+            store: Part::New(Storage::with_capacity),
+            admission: Part::New(|(capacity, config)| TinyLFUCache::with_config(capacity, &config)),
+            default_ttl: Duration::from_secs(0),
+            metrics: false,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Default for CacheBuilder<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, E, S, A, H> CacheBuilder<K, V, E, S, A, H>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+{
+    ///
+    /// Set max items in cache.
+    /// Capacity is required, unless store is given.
+    ///
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    ///
+    /// Set window size of default TinyLFU.
+    /// By default window size is 10 times capacity.
+    ///
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.window_size = Some(window_size);
+        self
+    }
+
+    ///
+    /// Set time to live of items inserted without explicit time to live
+    ///
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    ///
+    /// Activate metric collecting
+    ///
+    pub fn metrics(mut self, metrics: bool) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    ///
+    /// Set callback for evicted items from cache
+    ///
+    pub fn on_evict<U>(self, on_evict: U) -> CacheBuilder<K, V, U, S, A, H>
+    where
+        U: OnEvict<K, V>,
+    {
+        CacheBuilder {
+            capacity: self.capacity,
+            window_size: self.window_size,
+            on_evict: Some(on_evict),
+            hasher_builder: self.hasher_builder,
+            store: self.store,
+            admission: self.admission,
+            default_ttl: self.default_ttl,
+            metrics: self.metrics,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    ///
+    /// Set builder of hasher for keys of items
+    ///
+    pub fn hasher<U>(self, hasher_builder: U) -> CacheBuilder<K, V, E, S, A, U>
+    where
+        U: BuildHasher,
+    {
+        CacheBuilder {
+            capacity: self.capacity,
+            window_size: self.window_size,
+            on_evict: self.on_evict,
+            hasher_builder,
+            store: self.store,
+            admission: self.admission,
+            default_ttl: self.default_ttl,
+            metrics: self.metrics,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    ///
+    /// Set store for items. Capacity of cache is given by store.
+    ///
+    pub fn store<U>(self, store: U) -> CacheBuilder<K, V, E, U, A, H>
+    where
+        U: Store<K, V>,
+    {
+        CacheBuilder {
+            capacity: self.capacity,
+            window_size: self.window_size,
+            on_evict: self.on_evict,
+            hasher_builder: self.hasher_builder,
+            store: Part::Custom(store),
+            admission: self.admission,
+            default_ttl: self.default_ttl,
+            metrics: self.metrics,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    ///
+    /// Set admission policy. Window size can not be set together with custom admission.
+    ///
+    pub fn admission<U>(self, admission: U) -> CacheBuilder<K, V, E, S, U, H>
+    where
+        U: TinyLFU,
+    {
+        CacheBuilder {
+            capacity: self.capacity,
+            window_size: self.window_size,
+            on_evict: self.on_evict,
+            hasher_builder: self.hasher_builder,
+            store: self.store,
+            admission: Part::Custom(admission),
+            default_ttl: self.default_ttl,
+            metrics: self.metrics,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    ///
+    /// Validate configuration and create cache
+    ///
+    /// # Errors
+    ///
+    /// If capacity is missing, zero or different from capacity of given store.
+    /// If window size is zero or it is set together with custom admission.
+    ///
+    pub fn build(self) -> Result<Cache<K, V, E, S, A, H>, BuildError> {
+        let store = match self.store {
+            Part::Custom(store) => match self.capacity {
+                Some(capacity) if capacity != store.capacity() => {
+                    return Err(BuildError::CapacityMismatch {
+                        capacity,
+                        store: store.capacity(),
+                    })
+                }
+                _ => store,
+            },
+            Part::New(new) => match self.capacity {
+                Some(0) => return Err(BuildError::ZeroCapacity),
+                Some(capacity) => new(capacity),
+                None => return Err(BuildError::MissingCapacity),
+            },
+        };
+        let capacity = store.capacity();
+        if capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        let admission = match (self.admission, self.window_size) {
+            (_, Some(0)) => return Err(BuildError::ZeroWindowSize),
+            (Part::Custom(_), Some(_)) => return Err(BuildError::WindowSizeWithAdmission),
+            (Part::Custom(admission), None) => admission,
+            (Part::New(new), window_size) => {
+                let config = match window_size {
+                    Some(window_size) => TinyLFUConfig::default().with_window_size(window_size),
+                    None => TinyLFUConfig::default(),
+                };
+                new((capacity, config))
+            }
+        };
+        let cache = Cache::from_parts(
+            store,
+            Arc::new(Mutex::new(admission)),
+            self.on_evict,
+            self.hasher_builder,
+            UnitWeigher,
+        )
+        .with_default_ttl(self.default_ttl);
+        if self.metrics {
+            cache.enable_metrics();
+        }
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::CacheBuilder;
+    use crate::cache::{Cache, OnEvict, RemovalCause};
+    use crate::clock::ManualClock;
+    use crate::error::BuildError;
+    use crate::policy::{LruPolicy, SampledPolicy};
+    use crate::store::Storage;
+    use crate::tiny_lfu::{CompactTinyLFU, TinyLFU, VoidTinyLFU};
+    use std::collections::hash_map::RandomState;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct RecordEvict {
+        evicted: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl OnEvict<u64, u64> for RecordEvict {
+        fn evict(&self, k: u64, _v: u64, _cause: RemovalCause) {
+            self.evicted.lock().unwrap().push(k);
+        }
+    }
+
+    #[test]
+    fn build() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = CacheBuilder::new()
+            .capacity(10)
+            .window_size(100)
+            .on_evict(RecordEvict {
+                evicted: evicted.clone(),
+            })
+            .hasher(RandomState::new())
+            .metrics(true)
+            .build()
+            .unwrap();
+        assert_eq!(cache.capacity(), 10);
+        assert_eq!(cache.admit.lock().unwrap().window_size(), 100);
+        assert!(cache.metrics().is_some());
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.invalidate(&1));
+        assert_eq!(*evicted.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn custom_parts() {
+        let cache: Cache<u64, u64, _, _, _> = CacheBuilder::new()
+            .store(Storage::with_capacity(20))
+            .admission(CompactTinyLFU::new(20))
+            .build()
+            .unwrap();
+        assert_eq!(cache.capacity(), 20);
+        assert_eq!(cache.admit.lock().unwrap().window_size(), 200);
+    }
+
+    #[test]
+    fn default_ttl() {
+        let clock = ManualClock::new();
+        let mut cache: Cache<u64, u64, _, _> = CacheBuilder::new()
+            .store(Storage::with_clock(10, SampledPolicy, clock.clone()))
+            .default_ttl(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert_with_ttl(2, 2, Duration::from_secs(20)).is_ok());
+        clock.advance(Duration::from_secs(11));
+        assert!(!cache.contains(&1));
+        assert!(cache.contains(&2));
+    }
+
+    #[test]
+    fn classic_policy() {
+        let clock = ManualClock::new();
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let mut cache: Cache<u64, u64, _, _, _> = CacheBuilder::new()
+            .store(Storage::with_clock(2, LruPolicy::new(), clock.clone()))
+            .admission(VoidTinyLFU)
+            .on_evict(RecordEvict {
+                evicted: evicted.clone(),
+            })
+            .default_ttl(Duration::from_secs(10))
+            .metrics(true)
+            .build()
+            .unwrap();
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        assert_eq!(cache.get(&1), Some(&1));
+        assert!(cache.insert(3, 3).is_ok());
+        assert_eq!(*evicted.lock().unwrap(), vec![2]);
+        clock.advance(Duration::from_secs(11));
+        assert!(!cache.contains(&1));
+        assert!(cache.metrics().is_some());
+    }
+
+    #[test]
+    fn validation() {
+        let err = CacheBuilder::<u64, u64>::new().build().err();
+        assert_eq!(err, Some(BuildError::MissingCapacity));
+        let err = CacheBuilder::<u64, u64>::new().capacity(0).build().err();
+        assert_eq!(err, Some(BuildError::ZeroCapacity));
+        let err = CacheBuilder::<u64, u64>::new()
+            .capacity(10)
+            .window_size(0)
+            .build()
+            .err();
+        assert_eq!(err, Some(BuildError::ZeroWindowSize));
+        let err = CacheBuilder::<u64, u64>::new()
+            .capacity(10)
+            .store(Storage::with_capacity(20))
+            .build()
+            .err();
+        assert_eq!(
+            err,
+            Some(BuildError::CapacityMismatch {
+                capacity: 10,
+                store: 20
+            })
+        );
+        let err = CacheBuilder::<u64, u64>::new()
+            .capacity(10)
+            .window_size(100)
+            .admission(CompactTinyLFU::new(10))
+            .build()
+            .err();
+        assert_eq!(err, Some(BuildError::WindowSizeWithAdmission));
+    }
+}
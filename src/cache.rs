@@ -1,15 +1,87 @@
-use crate::iter::Iter;
+use crate::admission::{AdmissionDecision, AdmissionLog, AdmissionRecord};
+use crate::builder::CacheBuilder;
+use crate::climber::HillClimber;
+use crate::clock::Clock;
+use crate::entry::{Entry, OccupiedEntry, VacantEntry};
+use crate::error::InsertError;
+#[cfg(feature = "serde")]
+use crate::error::SnapshotError;
+use crate::expiry::{Expiry, VoidExpiry};
+use crate::iter::{Drain, IntoIter, Iter, IterMut, Keys, Values};
 use crate::metrics::{MetricType, Metrics};
+use crate::policy::{
+    ArcPolicy, FifoPolicy, LfuPolicy, LirsPolicy, LruPolicy, S3FifoPolicy, SampledPolicy,
+    WindowTinyLFUPolicy,
+};
+#[cfg(feature = "serde")]
+use crate::snapshot::{
+    since_epoch, AdmissionHeader, Snapshot, SnapshotItem, ADMISSION_VERSION, SNAPSHOT_VERSION,
+};
 use crate::store::{Item, SampleItem, Storage, Store};
-use crate::tiny_lfu::{TinyLFU, TinyLFUCache, MAX_WINDOW_SIZE};
+use crate::tiny_lfu::{CompactTinyLFU, TinyLFU, TinyLFUCache, TinyLFUConfig, VoidTinyLFU};
 use probabilistic_collections::SipHasherBuilder;
-use std::hash::{BuildHasher, Hash, Hasher};
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Serialize};
+use std::hash::{BuildHasher, Hash};
+#[cfg(feature = "serde")]
+use std::io::{Read, Write};
 use std::marker::PhantomData;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+///
+/// Max number of victims sampled for one incoming item, so insert of costly item does bounded work
+///
+const MAX_VICTIMS: usize = 64;
+
+///
+/// Reason why item was removed from cache
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RemovalCause {
+    ///
+    /// Time to live or time to idle of item elapsed
+    ///
+    Expired,
+
+    ///
+    /// Item was evicted to release room for other item
+    ///
+    Size,
+
+    ///
+    /// Item was removed or invalidated by user
+    ///
+    Explicit,
+
+    ///
+    /// Value of item was replaced by insert, or item was replaced by other item with same key hash
+    ///
+    Replaced,
+
+    ///
+    /// Item was removed when whole cache was cleared
+    ///
+    Cleared,
+}
+
+impl RemovalCause {
+    ///
+    /// Returns true if item was removed by cache itself, not by user
+    ///
+    pub fn was_evicted(&self) -> bool {
+        matches!(self, RemovalCause::Expired | RemovalCause::Size)
+    }
+}
+
+///
+/// Callback for items removed from cache.
+///
+/// Callback gets ownership of items which are dropped by cache.
+/// Value removed by `remove` or replaced by `insert` is moved to callback instead of returning it to the caller.
+///
 pub trait OnEvict<K, V> {
-    fn evict(&self, k: &K, v: &V);
+    fn evict(&self, k: K, v: V, cause: RemovalCause);
 }
 
 pub struct VoidEvict<K, V> {
@@ -18,7 +90,44 @@ pub struct VoidEvict<K, V> {
 }
 
 impl<K, V> OnEvict<K, V> for VoidEvict<K, V> {
-    fn evict(&self, _k: &K, _v: &V) {}
+    fn evict(&self, _k: K, _v: V, _cause: RemovalCause) {}
+}
+
+///
+/// Calculate cost of item in cache
+///
+pub trait Weigher<K, V> {
+    fn weight(&self, k: &K, v: &V) -> usize;
+}
+
+///
+/// Every item in cache has cost 1, so capacity is number of items.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnitWeigher;
+
+impl<K, V> Weigher<K, V> for UnitWeigher {
+    fn weight(&self, _k: &K, _v: &V) -> usize {
+        1
+    }
+}
+
+impl<K, V, F> Weigher<K, V> for F
+where
+    F: Fn(&K, &V) -> usize,
+{
+    fn weight(&self, k: &K, v: &V) -> usize {
+        self(k, v)
+    }
+}
+
+impl<K, V, E> OnEvict<K, V> for Arc<E>
+where
+    E: OnEvict<K, V>,
+{
+    fn evict(&self, k: K, v: V, cause: RemovalCause) {
+        self.as_ref().evict(k, v, cause)
+    }
 }
 
 ///
@@ -31,25 +140,50 @@ pub struct Cache<
     S = Storage<K, V>,
     A = TinyLFUCache,
     H = SipHasherBuilder,
+    W = UnitWeigher,
+    X = VoidExpiry,
 > where
     K: Eq + Hash,
     E: OnEvict<K, V>,
     S: Store<K, V>,
     A: TinyLFU,
     H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
 {
     hasher_builder: H,
     pub(crate) store: S,
-    admit: Mutex<A>,
+    pub(crate) admit: Arc<Mutex<A>>,
     on_evict: Option<E>,
+    weigher: W,
+    expiry: Option<X>,
+    default_ttl: Duration,
     metrics: Mutex<Option<Metrics>>,
+    admission_log: Option<AdmissionLog>,
+    climber: Option<Mutex<HillClimber>>,
     _k: PhantomData<K>,
     _v: PhantomData<V>,
 }
 
 impl<K: Eq + Hash, V> Cache<K, V> {
     ///
-    /// Create new cache with default `window_size` = 10000 for TinyLFU.
+    /// Returns builder of cache with default parts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8, u8>::builder().capacity(100).build().unwrap();
+    /// assert_eq!(cache.capacity(), 100);
+    /// ```
+    ///
+    pub fn builder() -> CacheBuilder<K, V> {
+        CacheBuilder::new()
+    }
+
+    ///
+    /// Create new cache with default TinyLFU config, where window size is 10 times `capacity`.
     ///
     /// # Arguments
     ///
@@ -68,7 +202,7 @@ impl<K: Eq + Hash, V> Cache<K, V> {
     /// If `capacity` is 0.
     ///
     pub fn new(capacity: usize) -> Self {
-        Self::with_window_size(capacity, MAX_WINDOW_SIZE)
+        Self::with_config(capacity, TinyLFUConfig::default())
     }
 
     ///
@@ -76,7 +210,7 @@ impl<K: Eq + Hash, V> Cache<K, V> {
     ///
     /// # Arguments
     ///
-    /// - `window_size`: window size for TinyLFU (max. 10 000)
+    /// - `window_size`: window size for TinyLFU
     ///- `capacity`: max items in cache
     ///
     /// # Example
@@ -90,272 +224,1648 @@ impl<K: Eq + Hash, V> Cache<K, V> {
     /// # Panic
     ///
     /// If `window_size` or `capacity` is 0.
-    /// If `window_size` > 10 000
     ///
     pub fn with_window_size(capacity: usize, window_size: usize) -> Self {
         assert_ne!(window_size, 0);
-        assert!(window_size <= 10_000);
+        Self::with_config(
+            capacity,
+            TinyLFUConfig::default().with_window_size(window_size),
+        )
+    }
+
+    ///
+    /// Create new cache with defined accuracy and window size of TinyLFU.
+    ///
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
+    ///- `config`: TinyLFU config
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, TinyLFUConfig};
+    ///
+    /// let cache = Cache::<u8,u8>::with_config(100, TinyLFUConfig::default().with_error(0.05));
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_config(capacity: usize, config: TinyLFUConfig) -> Self {
         assert_ne!(capacity, 0);
         Self {
-            _k: PhantomData::default(),
-            _v: PhantomData::default(),
+            _k: PhantomData,
+            _v: PhantomData,
             metrics: Mutex::new(None),
+            admission_log: None,
+            climber: None,
+            weigher: UnitWeigher,
+            expiry: None,
+            default_ttl: Duration::from_secs(0),
             on_evict: None,
-            admit: Mutex::new(TinyLFUCache::new(window_size)),
+            admit: Arc::new(Mutex::new(TinyLFUCache::with_config(capacity, &config))),
             store: Storage::with_capacity(capacity),
             hasher_builder: SipHasherBuilder::from_entropy(),
         }
     }
 }
 
-impl<K, V, E> Cache<K, V, E>
-where
-    K: Eq + Hash,
-    E: OnEvict<K, V>,
-{
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, WindowTinyLFUPolicy>> {
+    ///
+    /// Create new cache with W-TinyLFU layout and default TinyLFU config.
     ///
-    /// Create new cache with TinyLFU window size = 10 000 and callback for evicted items from cache.
+    /// New items are admitted into small LRU window (1% of capacity) in front of segmented LRU main space.
+    /// TinyLFU estimate decide if the oldest item from window is promoted into main space over the oldest item from probation segment.
     ///
     /// # Arguments
     ///
-    /// - `capacity`: max items in cache
-    /// - `on_evict`: will be call for every item evicted from cache.
+    ///- `capacity`: max items in cache
     ///
     /// # Example
     ///
     /// ```
-    /// use cascara::{Cache, OnEvict};
-    ///
-    /// #[derive(Default)]
-    /// struct Evict {}
-    ///
-    /// impl OnEvict<u8, u8> for Evict {
-    ///     fn evict(&self, k: &u8, v: &u8) {
-    ///         println!("Evict item.  k={}, v={}", k, v);
-    ///     }
-    /// }
+    /// use cascara::Cache;
     ///
-    /// let cache = Cache::<u8,u8,Evict>::with_on_evict(100, Evict::default());
+    /// let mut cache = Cache::<u8,u8,_,_>::with_admission_window(2);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert!(cache.insert(3, 3).is_ok());
+    /// assert_eq!(cache.len(), 2);
     /// ```
     ///
     /// # Panic
     ///
-    /// If  `capacity` is 0.
+    /// If `capacity` is 0.
     ///
-    pub fn with_on_evict(capacity: usize, on_evict: E) -> Self {
-        Self::with_on_evict_and_window_size(capacity, on_evict, MAX_WINDOW_SIZE)
+    pub fn with_admission_window(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, WindowTinyLFUPolicy::with_capacity(capacity)),
+            Arc::new(Mutex::new(TinyLFUCache::with_config(
+                capacity,
+                &TinyLFUConfig::default(),
+            ))),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
     }
+}
 
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V>, CompactTinyLFU> {
     ///
-    /// Create new cache with defined window size for TinyLFU and callback for evicted items from cache.
+    /// Create new cache with compact TinyLFU, which keeps frequencies in 4-bit counters.
     ///
     /// # Arguments
     ///
-    /// - `capacity`: max items in cache
-    /// - `on_evict`: will be call for every item evicted from cache.
-    /// - `window_size`: window size for TinyLFU (max. 10 000)
+    ///- `capacity`: max items in cache
+    ///- `config`: TinyLFU config
     ///
     /// # Example
     ///
     /// ```
-    /// use cascara::{Cache, OnEvict};
-    ///
-    /// #[derive(Default)]
-    /// struct Evict {}
-    ///
-    /// impl OnEvict<u8, u8> for Evict {
-    ///     fn evict(&self, k: &u8, v: &u8) {
-    ///         println!("Evict item.  k={}, v={}", k, v);
-    ///     }
-    /// }
+    /// use cascara::{Cache, TinyLFUConfig};
     ///
-    /// let cache = Cache::<u8,u8,Evict>::with_on_evict_and_window_size(100, Evict::default(), 20);
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_compact_sketch(100, TinyLFUConfig::default());
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert_eq!(cache.get(&1), Some(&1));
     /// ```
     ///
     /// # Panic
     ///
-    /// If `window_size` or `capacity` is 0.
-    /// If `window_size` > 10 000
+    /// If `capacity` is 0.
     ///
-    pub fn with_on_evict_and_window_size(capacity: usize, on_evict: E, window_size: usize) -> Self {
-        assert_ne!(window_size, 0);
-        assert!(window_size <= 10_000);
+    pub fn with_compact_sketch(capacity: usize, config: TinyLFUConfig) -> Self {
         assert_ne!(capacity, 0);
-        Self {
-            _k: PhantomData::default(),
-            _v: PhantomData::default(),
-            metrics: Mutex::new(None),
-            on_evict: Some(on_evict),
-            admit: Mutex::new(TinyLFUCache::new(window_size)),
-            store: Storage::with_capacity(capacity),
-            hasher_builder: SipHasherBuilder::from_entropy(),
-        }
+        Self::from_parts(
+            Storage::with_capacity(capacity),
+            Arc::new(Mutex::new(CompactTinyLFU::with_config(capacity, &config))),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
     }
 }
 
-impl<K, V, E, S, A, H> Cache<K, V, E, S, A, H>
-where
-    K: Eq + Hash,
-    E: OnEvict<K, V>,
-    S: Store<K, V>,
-    A: TinyLFU,
-    H: BuildHasher,
-{
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, LruPolicy>, VoidTinyLFU> {
     ///
-    /// Calculate hash for given item key
+    /// Create new cache with strict LRU eviction and without TinyLFU admission.
     ///
-    fn key_hash(&self, k: &K) -> u64 {
-        let mut hasher = self.hasher_builder.build_hasher();
-        k.hash(&mut hasher);
-        hasher.finish()
-    }
-
+    /// Incoming items are always admitted and the least recently used item is evicted.
     ///
-    /// Remove selected victim from storage.
-    ///
-    fn remove_victim(&mut self, victim: Option<SampleItem>) {
-        if let Some(victim) = victim {
-            if let Some(removed) = self.store.remove(&victim.key) {
-                let k = self.key_hash(&removed.k);
-                let mut metrics = self.metrics.lock().unwrap();
-                if let Some(metrics) = &mut *metrics {
-                    metrics.insert(MetricType::KeyEvict, &k, 1);
-                }
-                if let Some(on_evict) = &self.on_evict {
-                    on_evict.evict(&removed.k, &removed.v);
-                }
-            }
-        }
-    }
-
+    /// # Arguments
     ///
-    /// Insert item into storage
+    ///- `capacity`: max items in cache
     ///
-    fn insert_item_with_ttl(
-        &mut self,
-        k: u64,
-        item: Item<K, V>,
-        expiration: Duration,
-    ) -> Option<V> {
-        if let Some(old_item) = self.store.insert_with_ttl(k, item, expiration) {
-            Some(old_item.v)
-        } else {
-            None
-        }
-    }
-
+    /// # Example
     ///
-    /// Check if item can be inserted.
+    /// ```
+    /// use cascara::Cache;
     ///
-    /// Item can be inserted if:
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_lru(2);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// assert!(cache.insert(3, 3).is_ok());
+    /// assert!(cache.contains(&1));
+    /// assert!(!cache.contains(&2));
+    /// ```
     ///
-    /// - there is a room
-    /// - incoming item estimate if bigger than sample item from cache
+    /// # Panic
     ///
-    /// Insertion check can return victim which should be removed from cache
+    /// If `capacity` is 0.
     ///
-    fn can_be_insert(&mut self, k: &u64) -> Result<Option<SampleItem>, Option<SampleItem>> {
-        //no need to find victims if already in cache
-        if self.store.contains(k) {
-            let mut metrics = self.metrics.lock().unwrap();
-            if let Some(metrics) = &mut *metrics {
-                metrics.insert(MetricType::KeyUpdate, &k, 1);
-            }
-            return Ok(None);
-        }
-
-        //insert item to cache if there is enough space
-        if self.store.room_left() > 0 {
-            return Ok(None);
-        }
-
-        //try find victim and check if incoming item estimate is enough
-        let admit = self.admit.lock().unwrap();
-        let incoming_estimate = admit.estimate(&k);
-
-        let victim = self.store.sample(&*admit);
-        if let Some(victim) = victim {
-            if incoming_estimate < victim.estimate {
-                Err(Some(victim))
-            } else {
-                Ok(Some(victim))
-            }
-        } else {
-            unreachable!()
-        }
+    pub fn with_lru(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, LruPolicy::new()),
+            Arc::new(Mutex::new(VoidTinyLFU)),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
     }
+}
 
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, LfuPolicy>, VoidTinyLFU> {
     ///
-    /// Activate metric collecting in cache
+    /// Create new cache with exact LFU eviction and without TinyLFU admission.
+    ///
+    /// Incoming items are always admitted and the least frequently used item is evicted.
+    /// Ties are broken by recency.
+    ///
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let cache = Cache::<u8,u8>::new(100).with_metrics();
-    /// assert!(cache.metrics().is_some());
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_lfu(2);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.get(&2), Some(&2));
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// assert!(cache.insert(3, 3).is_ok());
+    /// assert!(cache.contains(&1));
+    /// assert!(!cache.contains(&2));
     /// ```
     ///
-    pub fn with_metrics(self) -> Self {
-        {
-            let mut metrics = self.metrics.lock().unwrap();
-            metrics.replace(Metrics::new());
-        }
-        self
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_lfu(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, LfuPolicy::new()),
+            Arc::new(Mutex::new(VoidTinyLFU)),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
     }
+}
 
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, FifoPolicy>, VoidTinyLFU> {
+    ///
+    /// Create new cache with FIFO eviction and without TinyLFU admission.
+    ///
+    /// Incoming items are always admitted and the oldest inserted item is evicted.
     ///
-    /// Returns how many items can be hold in cache
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let cache = Cache::<u8,u8>::new(100);
-    /// assert_eq!(cache.capacity(), 100);
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_fifo(2);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// assert!(cache.insert(3, 3).is_ok());
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
     /// ```
     ///
-    pub fn capacity(&self) -> usize {
-        self.store.capacity()
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_fifo(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, FifoPolicy::new()),
+            Arc::new(Mutex::new(VoidTinyLFU)),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
     }
+}
 
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, ArcPolicy>, VoidTinyLFU> {
     ///
-    /// Returns actual number of items in cache
+    /// Create new cache with ARC eviction and without TinyLFU admission.
+    ///
+    /// Recency and frequency lists are balanced by history of evicted items.
+    /// Target size of recency list is reported as `arc_p` policy state in metrics.
+    ///
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert(1,1).is_ok());
-    /// assert_eq!(cache.len(), 1);
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_arc(2).with_metrics();
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// assert!(cache.insert(3, 3).is_ok());
+    /// assert!(cache.contains(&1));
+    /// assert!(!cache.contains(&2));
+    /// let metrics = cache.metrics().unwrap();
+    /// assert_eq!(metrics.policy_state("arc_p"), Some(0));
+    /// assert_eq!(metrics.policy_state("arc_b1"), Some(1));
     /// ```
     ///
-    pub fn len(&self) -> usize {
-        self.store.len()
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_arc(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, ArcPolicy::with_capacity(capacity)),
+            Arc::new(Mutex::new(VoidTinyLFU)),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
     }
+}
 
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, LirsPolicy>, VoidTinyLFU> {
     ///
-    /// Returns true if cache is empty
+    /// Create new cache with LIRS eviction and without TinyLFU admission.
     ///
-    /// # Example
+    /// Items with low inter-reference recency hold 99% of capacity, so scans do not flush them.
     ///
-    /// ```
-    /// use cascara::Cache;
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_lirs(3);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// for i in 10..20 {
+    ///     assert!(cache.insert(i, i).is_ok());
+    /// }
+    /// assert!(cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_lirs(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, LirsPolicy::with_capacity(capacity)),
+            Arc::new(Mutex::new(VoidTinyLFU)),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
+    }
+}
+
+impl<K: Eq + Hash, V> Cache<K, V, VoidEvict<K, V>, Storage<K, V, S3FifoPolicy>, VoidTinyLFU> {
+    ///
+    /// Create new cache with S3-FIFO eviction and without TinyLFU admission.
+    ///
+    /// New items pass through small FIFO queue (10% of capacity) and only accessed ones are moved into main FIFO queue.
+    ///
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::<u8,u8,_,_,_>::with_s3_fifo(3);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// for i in 10..20 {
+    ///     assert!(cache.insert(i, i).is_ok());
+    /// }
+    /// assert!(cache.contains(&1));
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_s3_fifo(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_policy(capacity, S3FifoPolicy::with_capacity(capacity)),
+            Arc::new(Mutex::new(VoidTinyLFU)),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
+    }
+}
+
+impl<K, V, C> Cache<K, V, VoidEvict<K, V>, Storage<K, V, SampledPolicy, C>>
+where
+    K: Eq + Hash,
+    C: Clock,
+{
+    ///
+    /// Create new cache with default TinyLFU config and clock used for expiration of items.
+    ///
+    /// # Arguments
+    ///
+    ///- `capacity`: max items in cache
+    ///- `clock`: source of time for expiration of items
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone());
+    /// assert!(cache.insert_with_ttl(1, 1, Duration::from_secs(1)).is_ok());
+    /// clock.advance(Duration::from_secs(2));
+    /// assert!(!cache.contains(&1));
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_clock(capacity: usize, clock: C) -> Self {
+        assert_ne!(capacity, 0);
+        Self::from_parts(
+            Storage::with_clock(capacity, SampledPolicy, clock),
+            Arc::new(Mutex::new(TinyLFUCache::with_config(
+                capacity,
+                &TinyLFUConfig::default(),
+            ))),
+            None,
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
+    }
+}
+
+impl<K, V, E> Cache<K, V, E>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+{
+    ///
+    /// Create new cache with default TinyLFU config and callback for evicted items from cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `on_evict`: will be call for every item evicted from cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, OnEvict, RemovalCause};
+    ///
+    /// #[derive(Default)]
+    /// struct Evict {}
+    ///
+    /// impl OnEvict<u8, u8> for Evict {
+    ///     fn evict(&self, k: u8, v: u8, cause: RemovalCause) {
+    ///         println!("Evict item.  k={}, v={}, cause={:?}", k, v, cause);
+    ///     }
+    /// }
+    ///
+    /// let cache = Cache::<u8,u8,Evict>::with_on_evict(100, Evict::default());
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If  `capacity` is 0.
+    ///
+    pub fn with_on_evict(capacity: usize, on_evict: E) -> Self {
+        let window_size = TinyLFUConfig::default().window_size(capacity);
+        Self::with_on_evict_and_window_size(capacity, on_evict, window_size)
+    }
+
+    ///
+    /// Create new cache with defined window size for TinyLFU and callback for evicted items from cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `on_evict`: will be call for every item evicted from cache.
+    /// - `window_size`: window size for TinyLFU
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, OnEvict, RemovalCause};
+    ///
+    /// #[derive(Default)]
+    /// struct Evict {}
+    ///
+    /// impl OnEvict<u8, u8> for Evict {
+    ///     fn evict(&self, k: u8, v: u8, cause: RemovalCause) {
+    ///         println!("Evict item.  k={}, v={}, cause={:?}", k, v, cause);
+    ///     }
+    /// }
+    ///
+    /// let cache = Cache::<u8,u8,Evict>::with_on_evict_and_window_size(100, Evict::default(), 20);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `window_size` or `capacity` is 0.
+    ///
+    pub fn with_on_evict_and_window_size(capacity: usize, on_evict: E, window_size: usize) -> Self {
+        assert_ne!(window_size, 0);
+        assert_ne!(capacity, 0);
+        Self {
+            _k: PhantomData,
+            _v: PhantomData,
+            metrics: Mutex::new(None),
+            admission_log: None,
+            climber: None,
+            weigher: UnitWeigher,
+            expiry: None,
+            default_ttl: Duration::from_secs(0),
+            on_evict: Some(on_evict),
+            admit: Arc::new(Mutex::new(TinyLFUCache::new(window_size))),
+            store: Storage::with_capacity(capacity),
+            hasher_builder: SipHasherBuilder::from_entropy(),
+        }
+    }
+}
+
+impl<K, V, E, S, A, H, W, X> Cache<K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    ///
+    /// Create new cache from prepared parts.
+    ///
+    /// Admit policy can be shared between more caches.
+    ///
+    pub(crate) fn from_parts(
+        store: S,
+        admit: Arc<Mutex<A>>,
+        on_evict: Option<E>,
+        hasher_builder: H,
+        weigher: W,
+    ) -> Self {
+        Self {
+            _k: PhantomData,
+            _v: PhantomData,
+            metrics: Mutex::new(None),
+            admission_log: None,
+            climber: None,
+            weigher,
+            expiry: None,
+            default_ttl: Duration::from_secs(0),
+            on_evict,
+            admit,
+            store,
+            hasher_builder,
+        }
+    }
+
+    ///
+    /// Calculate hash for given item key.
+    /// Items, samples and admission records are identified by this hash.
+    ///
+    pub fn key_hash(&self, k: &K) -> u64 {
+        self.hasher_builder.hash_one(k)
+    }
+
+    ///
+    /// Remove selected victims from storage.
+    /// Victim with key hash of incoming item is colliding item, which is replaced.
+    ///
+    fn remove_victims(&mut self, key_hash: &u64, victims: impl IntoIterator<Item = SampleItem>) {
+        for victim in victims {
+            //colliding item is replaced, not evicted
+            let removed = if victim.key.eq(key_hash) {
+                self.store.remove(&victim.key)
+            } else {
+                self.store.evict(&victim.key)
+            };
+            if let Some(removed) = removed {
+                {
+                    let mut metrics = self.metrics.lock().unwrap();
+                    if let Some(metrics) = &mut *metrics {
+                        metrics.insert(MetricType::KeyEvict, &victim.key, 1);
+                    }
+                }
+                if let Some(on_evict) = &self.on_evict {
+                    let cause = if victim.key.eq(key_hash) {
+                        RemovalCause::Replaced
+                    } else {
+                        RemovalCause::Size
+                    };
+                    on_evict.evict(removed.k, removed.v, cause);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Move removed item to `on_evict`. Value is returned back only if cache has no callback.
+    ///
+    fn hand_over(&self, k: K, v: V, cause: RemovalCause) -> Option<V> {
+        match &self.on_evict {
+            Some(on_evict) => {
+                on_evict.evict(k, v, cause);
+                None
+            }
+            None => Some(v),
+        }
+    }
+
+    ///
+    /// Insert item into storage. Replaced value is moved to `on_evict` or returned if cache has no callback.
+    ///
+    fn insert_item_with_ttl(
+        &mut self,
+        k: u64,
+        item: Item<K, V>,
+        expiration: Duration,
+    ) -> Option<V> {
+        let old_item = self.store.insert_with_ttl(k, item, expiration)?;
+        self.hand_over(old_item.k, old_item.v, RemovalCause::Replaced)
+    }
+
+    ///
+    /// Remove item with already calculated key hash from storage.
+    /// Removed value is moved to `on_evict` or returned if cache has no callback.
+    ///
+    pub(crate) fn remove_hashed(&mut self, key_hash: &u64) -> Option<V> {
+        let item = self.store.remove(key_hash)?;
+        self.hand_over(item.k, item.v, RemovalCause::Explicit)
+    }
+
+    ///
+    /// Record hit or miss of looked up item
+    ///
+    fn record_lookup(&self, key_hash: &u64, found: bool) {
+        let mut metrics = self.metrics.lock().unwrap();
+        if let Some(metrics) = &mut *metrics {
+            if found {
+                metrics.insert(MetricType::Hit, key_hash, 1);
+            } else {
+                metrics.insert(MetricType::Miss, key_hash, 1);
+            }
+            if let Some(climber) = &self.climber {
+                climber.lock().unwrap().record(metrics, &self.admit);
+            }
+        }
+    }
+
+    ///
+    /// Returns remaining time to live of item, `None` if item never expires.
+    /// Zero is returned for item at its expiration time, which is not passed to expiry.
+    ///
+    fn remaining(&self, item: &Item<K, V>) -> Option<Duration> {
+        item.expiration_time()
+            .map(|expiration_time| expiration_time.saturating_duration_since(self.store.now()))
+    }
+
+    ///
+    /// Update time to live of read item with expiry
+    ///
+    fn expire_after_read(&self, key_hash: &u64, k: &K) {
+        if let Some(expiry) = &self.expiry {
+            if let Some(item) = self.find(key_hash, k) {
+                let remaining = match self.remaining(item) {
+                    Some(remaining) if remaining.is_zero() => return,
+                    remaining => remaining.unwrap_or_default(),
+                };
+                let expiration = expiry.expire_after_read(&item.k, &item.v, remaining);
+                if expiration != remaining {
+                    self.store.set_ttl(key_hash, expiration);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Calculate time to live of inserted item with expiry, if it is not defined at insert.
+    /// Old item at its expiration time is expired, so new item is created.
+    ///
+    fn expire_after_write(
+        &self,
+        key_hash: &u64,
+        item: &Item<K, V>,
+        expiration: Duration,
+    ) -> Duration {
+        match &self.expiry {
+            Some(expiry) if expiration.is_zero() => {
+                match self
+                    .find(key_hash, &item.k)
+                    .map(|old_item| self.remaining(old_item))
+                {
+                    Some(Some(remaining)) if remaining.is_zero() => {
+                        expiry.expire_after_create(&item.k, &item.v)
+                    }
+                    Some(remaining) => {
+                        expiry.expire_after_update(&item.k, &item.v, remaining.unwrap_or_default())
+                    }
+                    None => expiry.expire_after_create(&item.k, &item.v),
+                }
+            }
+            None if expiration.is_zero() => self.default_ttl,
+            _ => expiration,
+        }
+    }
+
+    ///
+    /// Return item from storage only if its full key is equal to given key.
+    ///
+    /// Storage is indexed only by key hash, so two different keys can collide.
+    ///
+    fn find(&self, key_hash: &u64, k: &K) -> Option<&Item<K, V>> {
+        self.store.peek(key_hash).filter(|item| item.k.eq(k))
+    }
+
+    ///
+    /// Decide if item can be inserted. Cache is not changed.
+    ///
+    /// Item can be inserted if:
+    ///
+    /// - there is a room for its cost
+    /// - incoming item estimate is not lower than combined estimate of sampled victims, which release enough room
+    ///
+    /// At most `MAX_VICTIMS` victims are sampled, item which needs more of them does not get room.
+    ///
+    /// Admitted item can have victims which should be removed from cache.
+    /// If other key with same hash is in cache, than it is returned as victim.
+    /// Rejected item can have victim with lowest estimate, which should be removed from cache.
+    ///
+    fn decide_admission(&self, key_hash: &u64, k: &K, cost: usize) -> AdmissionDecision {
+        //item which never fits into cache is rejected
+        if cost > self.store.capacity() {
+            return AdmissionDecision::TooCostly {
+                cost,
+                capacity: self.store.capacity(),
+            };
+        }
+
+        let mut victims = Vec::new();
+        let mut room = self.store.room_left();
+
+        //no need to find victims for item in cache if there is enough space
+        if let Some(item) = self.store.peek(key_hash) {
+            if item.k.ne(k) {
+                //colliding item has same estimate, so it is always replaced
+                let estimate = self.admit.lock().unwrap().estimate(key_hash);
+                victims.push(SampleItem::with_cost(*key_hash, estimate, item.cost));
+            }
+            room += item.cost;
+        }
+
+        //insert item to cache if there is enough space
+        if room >= cost {
+            return AdmissionDecision::Admitted { victims };
+        }
+
+        //try find victims and check if incoming item estimate is enough
+        let admit = self.admit.lock().unwrap();
+        let incoming_estimate = admit.estimate(key_hash);
+
+        let mut sampled = Vec::new();
+        let mut exclude = vec![*key_hash];
+        while room < cost && sampled.len() < MAX_VICTIMS {
+            if let Some(victim) = self.store.sample(&*admit, &exclude) {
+                room += victim.cost;
+                exclude.push(victim.key);
+                sampled.push(victim);
+            } else {
+                break;
+            }
+        }
+
+        if room < cost {
+            AdmissionDecision::NoRoom { cost, room }
+        } else if !self.store.admit(incoming_estimate, &sampled) {
+            AdmissionDecision::Rejected {
+                incoming_estimate,
+                victim_estimate: sampled.iter().map(|victim| victim.estimate).sum(),
+                victim: sampled.into_iter().min(),
+            }
+        } else {
+            victims.append(&mut sampled);
+            AdmissionDecision::Admitted { victims }
+        }
+    }
+
+    ///
+    /// Activate metric collecting in cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8,u8>::new(100).with_metrics();
+    /// assert!(cache.metrics().is_some());
+    /// ```
+    ///
+    pub fn with_metrics(self) -> Self {
+        self.enable_metrics();
+        self
+    }
+
+    ///
+    /// Adapt window size of TinyLFU to workload.
+    ///
+    /// Hit ratio is sampled from metrics (which are activated) every `period` lookups and window size is moved by hill climbing
+    /// towards better hit ratio, at most 4 times up or down from its actual size.
+    /// If TinyLFU is replaced (e.g. by `load_from`), bounds are derived again from its window size.
+    ///
+    /// # Arguments
+    ///
+    /// - `period`: number of lookups between two adjustments of window size
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8, u8>::with_window_size(100, 1000).with_adaptive_window(10);
+    /// for _ in 0..10 {
+    ///     assert_eq!(cache.get(&1), None);
+    /// }
+    /// assert_ne!(cache.window_size(), 1000);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `period` is 0.
+    ///
+    pub fn with_adaptive_window(mut self, period: usize) -> Self {
+        let window_size = self.window_size();
+        self.climber = Some(Mutex::new(HillClimber::new(period, window_size)));
+        self.with_metrics()
+    }
+
+    ///
+    /// Returns actual window size of TinyLFU
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8, u8>::with_window_size(100, 1000);
+    /// assert_eq!(cache.window_size(), 1000);
+    /// ```
+    ///
+    pub fn window_size(&self) -> usize {
+        self.admit.lock().unwrap().window_size()
+    }
+
+    ///
+    /// Returns estimated frequency of key in TinyLFU. Estimate is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8, u8>::new(100);
+    /// assert_eq!(cache.frequency(&1), 0);
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.frequency(&1), 1);
+    /// ```
+    ///
+    pub fn frequency(&self, k: &K) -> i64 {
+        let key_hash = self.key_hash(k);
+        self.admit.lock().unwrap().estimate(&key_hash)
+    }
+
+    ///
+    /// Explain if item with cost 1 would be admitted by insert. Cache is not changed.
+    ///
+    /// Victims are sampled randomly, so following insert can sample other victims.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{AdmissionDecision, Cache};
+    ///
+    /// let mut cache = Cache::new(1);
+    /// assert!(cache.would_admit(&1).is_admitted());
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// let _ = cache.get(&1);
+    /// match cache.would_admit(&2) {
+    ///     AdmissionDecision::Rejected { incoming_estimate, victim, victim_estimate } => {
+    ///         assert_eq!(incoming_estimate, 0);
+    ///         assert!(victim.is_some());
+    ///         assert!(victim_estimate > incoming_estimate);
+    ///     }
+    ///     decision => panic!("unexpected decision {:?}", decision),
+    /// }
+    /// ```
+    ///
+    pub fn would_admit(&self, k: &K) -> AdmissionDecision {
+        self.would_admit_with_cost(k, 1)
+    }
+
+    ///
+    /// Explain if item with given cost would be admitted by insert. Cache is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{AdmissionDecision, Cache};
+    ///
+    /// let cache = Cache::<u8, u8>::new(10);
+    /// assert_eq!(
+    ///     cache.would_admit_with_cost(&1, 11),
+    ///     AdmissionDecision::TooCostly { cost: 11, capacity: 10 }
+    /// );
+    /// ```
+    ///
+    pub fn would_admit_with_cost(&self, k: &K, cost: usize) -> AdmissionDecision {
+        let key_hash = self.key_hash(k);
+        self.decide_admission(&key_hash, k, cost)
+    }
+
+    ///
+    /// Record every n-th admission decision of insert into bounded log
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max number of kept records, the oldest records are dropped
+    /// - `every`: every n-th decision is recorded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(10).with_admission_log(100, 2);
+    /// for k in 0..4 {
+    ///     assert!(cache.insert(k, k).is_ok());
+    /// }
+    /// let records = cache.admission_log();
+    /// assert_eq!(records.len(), 2);
+    /// assert_eq!(records[0].key_hash, cache.key_hash(&1));
+    /// assert!(records[0].decision.is_admitted());
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` or `every` is 0.
+    ///
+    pub fn with_admission_log(mut self, capacity: usize, every: u64) -> Self {
+        self.admission_log = Some(AdmissionLog::new(capacity, every));
+        self
+    }
+
+    ///
+    /// Returns recorded admission decisions from the oldest one.
+    /// Log is empty if it is not activated by `with_admission_log`.
+    ///
+    pub fn admission_log(&self) -> Vec<AdmissionRecord> {
+        self.admission_log
+            .as_ref()
+            .map(|admission_log| admission_log.records())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// Start metric collecting in already created cache
+    ///
+    pub(crate) fn enable_metrics(&self) {
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics.replace(Metrics::new());
+    }
+
+    ///
+    /// Set weigher which calculates cost of items inserted by `insert` and `insert_with_ttl`.
+    ///
+    /// Capacity of cache is than max total cost of items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::<u8, String>::new(10).with_weigher(|_k: &u8, v: &String| v.len());
+    /// assert!(cache.insert(1, "abcd".to_string()).is_ok());
+    /// assert_eq!(cache.room_left(), 6);
+    /// ```
+    ///
+    pub fn with_weigher<U>(self, weigher: U) -> Cache<K, V, E, S, A, H, U, X>
+    where
+        U: Weigher<K, V>,
+    {
+        Cache {
+            _k: PhantomData,
+            _v: PhantomData,
+            metrics: self.metrics,
+            admission_log: self.admission_log,
+            climber: self.climber,
+            weigher,
+            expiry: self.expiry,
+            default_ttl: self.default_ttl,
+            on_evict: self.on_evict,
+            admit: self.admit,
+            store: self.store,
+            hasher_builder: self.hasher_builder,
+        }
+    }
+
+    ///
+    /// Set expiry which calculates time to live of items from their key and value.
+    ///
+    /// Expiry is asked when item is inserted without time to live and when item is read.
+    /// Explicit time to live defined at insert is not changed by expiry on insert.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, Expiry, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// struct Sliding;
+    ///
+    /// impl Expiry<u8, u8> for Sliding {
+    ///     fn expire_after_create(&self, _k: &u8, _v: &u8) -> Duration {
+    ///         Duration::from_secs(10)
+    ///     }
+    ///
+    ///     fn expire_after_read(&self, _k: &u8, _v: &u8, _remaining: Duration) -> Duration {
+    ///         Duration::from_secs(10)
+    ///     }
+    /// }
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone()).with_expiry(Sliding);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// clock.advance(Duration::from_secs(8));
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// clock.advance(Duration::from_secs(8));
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// clock.advance(Duration::from_secs(11));
+    /// assert!(!cache.contains(&1));
+    /// ```
+    ///
+    pub fn with_expiry<U>(self, expiry: U) -> Cache<K, V, E, S, A, H, W, U>
+    where
+        U: Expiry<K, V>,
+    {
+        Cache {
+            _k: PhantomData,
+            _v: PhantomData,
+            metrics: self.metrics,
+            admission_log: self.admission_log,
+            climber: self.climber,
+            weigher: self.weigher,
+            expiry: Some(expiry),
+            default_ttl: self.default_ttl,
+            on_evict: self.on_evict,
+            admit: self.admit,
+            store: self.store,
+            hasher_builder: self.hasher_builder,
+        }
+    }
+
+    ///
+    /// Set time to live of items inserted without explicit time to live and without expiry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone()).with_default_ttl(Duration::from_secs(10));
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert_with_ttl(2, 2, Duration::from_secs(20)).is_ok());
+    /// clock.advance(Duration::from_secs(11));
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// ```
+    ///
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    ///
+    /// Returns max total cost of items which can be hold in cache.
+    /// Without weigher every item has cost 1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8,u8>::new(100);
+    /// assert_eq!(cache.capacity(), 100);
+    /// ```
+    ///
+    pub fn capacity(&self) -> usize {
+        self.store.capacity()
+    }
+
+    ///
+    /// Returns actual number of items in cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1,1).is_ok());
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    ///
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    ///
+    /// Returns true if cache is empty
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let cache = Cache::<u8,u8>::new(100);
+    /// assert!(cache.is_empty());
+    /// ```
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
+    ///
+    /// Returns how many room (cost) left
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert_eq!(cache.room_left(), 100);
+    /// assert!(cache.insert(1,1).is_ok());
+    /// assert_eq!(cache.room_left(), 99);
+    /// ```
+    ///
+    pub fn room_left(&self) -> usize {
+        self.store.room_left()
+    }
+
+    ///
+    /// Return true if item is in storage
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.insert(1,1).is_ok());
+    /// assert!(cache.contains(&1));
+    /// ```
+    ///
+    pub fn contains(&self, k: &K) -> bool {
+        let key_hash = self.key_hash(k);
+        self.find(&key_hash, k).is_some()
+    }
+
+    ///
+    /// Return item ref if is in cache without counting lookup in TinyLFU and metrics
+    ///
+    pub(crate) fn peek(&self, k: &K) -> Option<&V> {
+        let key_hash = self.key_hash(k);
+        self.find(&key_hash, k).map(|item| &item.v)
+    }
+
+    ///
+    /// Return item ref if is in cache
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1,2).is_ok());
+    /// assert_eq!(cache.get(&1), Some(&2));
+    /// ```
+    ///
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let key_hash = self.key_hash(k);
+        {
+            let mut admit = self.admit.lock().unwrap();
+            admit.increment(&key_hash);
+        }
+        let result = self
+            .store
+            .get(&key_hash)
+            .filter(|item| item.k.eq(k))
+            .map(|item| &item.v);
+        if result.is_some() {
+            self.expire_after_read(&key_hash, k);
+        }
+        self.record_lookup(&key_hash, result.is_some());
+        result
+    }
+
+    ///
+    /// Return mutable item ref if is in cache
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
     ///
-    /// let cache = Cache::<u8,u8>::new(100);
-    /// assert!(cache.is_empty());
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1,1).is_ok());
+    /// let v = cache.get_mut(&1);
+    /// assert!(v.is_some());
+    /// if let Some(v) = v {
+    ///     *v = 2;
+    /// }
+    /// assert_eq!(cache.get(&1), Some(&2));
+    /// ```
+    ///
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let key_hash = self.key_hash(k);
+        {
+            let mut admit = self.admit.lock().unwrap();
+            admit.increment(&key_hash);
+        }
+        let found = self
+            .store
+            .get_mut(&key_hash)
+            .is_some_and(|item| item.k.eq(k));
+        if found {
+            self.expire_after_read(&key_hash, k);
+        }
+        self.record_lookup(&key_hash, found);
+        self.store
+            .peek_raw_mut(&key_hash)
+            .filter(|_| found)
+            .map(|item| &mut item.v)
+    }
+
+    ///
+    /// Insert item into cache. Item can be rejected (return Err) if  cache is full and estimate of new item is lower than sample item from cache.
+    /// If item is inserted, than preview item value can be returned. If cache has `on_evict`, preview value is moved to it
+    /// with `Replaced` cause and is not returned.
+    /// Cache is cleaned and all expired items are removed before new is inserted.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1,1).is_ok());
+    /// let v = cache.insert(1,2);
+    /// assert!(v.is_ok());
+    /// if let Ok(v) = v {
+    ///      assert_eq!(v, Some(1));
+    /// }
+    /// ```
+    ///
+    pub fn insert(&mut self, k: K, v: V) -> Result<Option<V>, InsertError<K, V>> {
+        self.insert_with_ttl(k, v, Duration::from_secs(0))
+    }
+
+    ///
+    /// Insert item into cache with defined time to life.
+    /// Returns preview item if exists with given key.
+    /// Cache is cleaned and all expired items are removed before new is inserted.
+    ///
+    /// If expiration is zero, than item is insert without ttl. Expiration has millisecond precision.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `expiration`: how long should item lives
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone());
+    /// assert!(cache.insert_with_ttl(1,1, Duration::from_secs(1)).is_ok());
+    /// assert!(cache.contains(&1));
+    /// clock.advance(Duration::from_secs(2));
+    /// assert!(!cache.contains(&1));
+    /// ```
+    ///
+    pub fn insert_with_ttl(
+        &mut self,
+        k: K,
+        v: V,
+        expiration: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        let cost = self.weigher.weight(&k, &v);
+        self.insert_item(Item::with_cost(k, v, cost), expiration)
+    }
+
+    ///
+    /// Insert item into cache with defined time to idle.
+    /// Item expires when it is not accessed (`get`, `get_mut`) for this time.
+    /// Cache is cleaned and all expired items are removed before new is inserted.
+    ///
+    /// If time to idle is zero, than item is insert without expiration. Expiration has millisecond precision.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `time_to_idle`: how long can item lives without access
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone());
+    /// assert!(cache.insert_with_tti(1, 1, Duration::from_secs(2)).is_ok());
+    /// clock.advance(Duration::from_secs(1));
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// clock.advance(Duration::from_secs(1));
+    /// assert_eq!(cache.get(&1), Some(&1));
+    /// clock.advance(Duration::from_secs(3));
+    /// assert!(!cache.contains(&1));
+    /// ```
+    ///
+    pub fn insert_with_tti(
+        &mut self,
+        k: K,
+        v: V,
+        time_to_idle: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.insert_with_ttl_and_tti(k, v, Duration::from_secs(0), time_to_idle)
+    }
+
+    ///
+    /// Insert item into cache with defined time to live and time to idle.
+    /// Item expires when it is not accessed for `time_to_idle` or after `expiration`, whichever comes first.
+    /// Cache is cleaned and all expired items are removed before new is inserted.
+    ///
+    /// Zero duration means no expiration of given kind. Expiration has millisecond precision.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `expiration`: how long should item lives
+    /// - `time_to_idle`: how long can item lives without access
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone());
+    /// let session = cache.insert_with_ttl_and_tti(
+    ///     1,
+    ///     "session",
+    ///     Duration::from_secs(8 * 60 * 60),
+    ///     Duration::from_secs(30 * 60),
+    /// );
+    /// assert!(session.is_ok());
+    /// for _ in 0..23 {
+    ///     clock.advance(Duration::from_secs(20 * 60));
+    ///     assert!(cache.get(&1).is_some());
+    /// }
+    /// clock.advance(Duration::from_secs(21 * 60));
+    /// assert!(cache.get(&1).is_none());
+    /// ```
+    ///
+    pub fn insert_with_ttl_and_tti(
+        &mut self,
+        k: K,
+        v: V,
+        expiration: Duration,
+        time_to_idle: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        let cost = self.weigher.weight(&k, &v);
+        let item = Item::with_cost(k, v, cost).with_time_to_idle(time_to_idle);
+        self.insert_item(item, expiration)
+    }
+
+    ///
+    /// Insert item with defined cost into cache. Weigher is not used for this item.
+    /// Item is rejected if its cost is bigger than capacity of cache
+    /// or if its estimate is lower than combined estimate of victims, which have to be removed to release enough room.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `cost`: item cost
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert_with_cost(1, 1, 60).is_ok());
+    /// assert_eq!(cache.room_left(), 40);
+    /// assert!(cache.insert_with_cost(2, 2, 101).is_err());
+    /// ```
+    ///
+    pub fn insert_with_cost(
+        &mut self,
+        k: K,
+        v: V,
+        cost: usize,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.insert_item(Item::with_cost(k, v, cost), Duration::from_secs(0))
+    }
+
+    ///
+    /// Insert prepared item with defined time to life into cache.
+    ///
+    fn insert_item(
+        &mut self,
+        item: Item<K, V>,
+        expiration: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.store.cleanup(&self.on_evict);
+
+        let key_hash = self.key_hash(&item.k);
+        let result = self.insert_hashed(key_hash, item, expiration);
+        if result.is_ok() {
+            let mut admit = self.admit.lock().unwrap();
+            admit.increment(&key_hash);
+        }
+        result
+    }
+
+    ///
+    /// Insert item with already calculated key hash into cache.
+    /// Storage is not cleaned and TinyLFU is not incremented.
+    ///
+    pub(crate) fn insert_hashed(
+        &mut self,
+        key_hash: u64,
+        item: Item<K, V>,
+        expiration: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        let expiration = self.expire_after_write(&key_hash, &item, expiration);
+        let decision = self.decide_admission(&key_hash, &item.k, item.cost);
+        if !matches!(decision, AdmissionDecision::TooCostly { .. })
+            && self.find(&key_hash, &item.k).is_some()
+        {
+            let mut metrics = self.metrics.lock().unwrap();
+            if let Some(metrics) = &mut *metrics {
+                metrics.insert(MetricType::KeyUpdate, &key_hash, 1);
+            }
+        }
+        if let Some(admission_log) = &mut self.admission_log {
+            admission_log.record(key_hash, item.cost, &decision);
+        }
+        match decision {
+            AdmissionDecision::Admitted { victims } => {
+                self.remove_victims(&key_hash, victims);
+                {
+                    let mut metrics = self.metrics.lock().unwrap();
+                    if let Some(metrics) = &mut *metrics {
+                        metrics.insert(MetricType::KeyInsert, &key_hash, 1);
+                    }
+                }
+                Ok(self.insert_item_with_ttl(key_hash, item, expiration))
+            }
+            AdmissionDecision::TooCostly { cost, capacity } => Err(InsertError::TooCostly {
+                cost,
+                capacity,
+                k: item.k,
+                v: item.v,
+            }),
+            AdmissionDecision::NoRoom { cost, room } => Err(InsertError::NoRoom {
+                cost,
+                room,
+                k: item.k,
+                v: item.v,
+            }),
+            AdmissionDecision::Rejected {
+                victim,
+                incoming_estimate,
+                victim_estimate,
+            } => {
+                self.remove_victims(&key_hash, victim);
+                Err(InsertError::Rejected {
+                    k: item.k,
+                    v: item.v,
+                    incoming_estimate,
+                    victim_estimate,
+                })
+            }
+        }
+    }
+
+    ///
+    /// Gets the given key's corresponding entry in the cache for in-place manipulation.
+    /// Key is hashed only once and lookup is counted in TinyLFU only once.
+    /// Cache is cleaned and all expired items are removed before entry is returned.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert_eq!(cache.entry(1).or_insert(1), Ok(&mut 1));
+    /// cache.entry(1).and_modify(|v| *v += 1);
+    /// assert_eq!(cache.get(&1), Some(&2));
+    /// ```
+    ///
+    pub fn entry(&mut self, k: K) -> Entry<'_, K, V, E, S, A, H, W, X> {
+        self.store.cleanup(&self.on_evict);
+
+        let key_hash = self.key_hash(&k);
+        {
+            let mut admit = self.admit.lock().unwrap();
+            admit.increment(&key_hash);
+        }
+        let found = self.find(&key_hash, &k).is_some();
+        self.record_lookup(&key_hash, found);
+        if found {
+            //mark access only once, entry works with item without touching order
+            self.store.get_mut(&key_hash);
+            self.expire_after_read(&key_hash, &k);
+            Entry::Occupied(OccupiedEntry::new(self, key_hash, k))
+        } else {
+            Entry::Vacant(VacantEntry::new(self, key_hash, k))
+        }
+    }
+
+    ///
+    /// Calculate cost of item with weigher
+    ///
+    pub(crate) fn weight(&self, k: &K, v: &V) -> usize {
+        self.weigher.weight(k, v)
+    }
+
+    ///
+    /// Remove and return item from cache.
+    /// If cache has `on_evict`, value is moved to it with `Explicit` cause and is not returned.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1,2).is_ok());
+    /// assert!(cache.contains(&1));
+    /// assert_eq!(cache.remove(&1), Some(2));
+    /// assert!(!cache.contains(&1));
+    /// ```
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let key_hash = self.key_hash(k);
+        self.find(&key_hash, k)?;
+        self.remove_hashed(&key_hash)
+    }
+
+    ///
+    /// Remove item from cache and pass it to `on_evict` with `Explicit` cause.
+    /// Returns true if item was in cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1,2).is_ok());
+    /// assert!(cache.invalidate(&1));
+    /// assert!(!cache.invalidate(&1));
+    /// assert!(!cache.contains(&1));
+    /// ```
+    pub fn invalidate(&mut self, k: &K) -> bool {
+        let key_hash = self.key_hash(k);
+        if self.find(&key_hash, k).is_none() {
+            return false;
+        }
+        if let Some(item) = self.store.remove(&key_hash) {
+            if let Some(on_evict) = &self.on_evict {
+                on_evict.evict(item.k, item.v, RemovalCause::Explicit);
+            }
+        }
+        true
+    }
+
+    ///
+    /// Remove all expired items from cache and call `on_evict` for them.
+    ///
+    /// Expired items are otherwise removed only before insert, so read-mostly cache should run pending tasks periodically.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = Cache::with_clock(100, clock.clone());
+    /// assert!(cache.insert_with_ttl(1, 1, Duration::from_secs(1)).is_ok());
+    /// clock.advance(Duration::from_secs(2));
+    /// assert_eq!(cache.len(), 1);
+    /// cache.run_pending_tasks();
+    /// assert_eq!(cache.len(), 0);
     /// ```
     ///
-    pub fn is_empty(&self) -> bool {
-        self.store.is_empty()
+    pub fn run_pending_tasks(&mut self) {
+        self.store.cleanup(&self.on_evict);
     }
 
     ///
-    /// Returns how many room left
+    /// Remove all items from cache.
     ///
     /// # Example
     ///
@@ -363,323 +1873,506 @@ where
     /// use cascara::Cache;
     ///
     /// let mut cache = Cache::new(100);
-    /// assert_eq!(cache.room_left(), 100);
     /// assert!(cache.insert(1,1).is_ok());
-    /// assert_eq!(cache.room_left(), 99);
+    /// assert!(cache.insert(2,2).is_ok());
+    /// assert!(cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// cache.clear();
+    /// assert!(!cache.contains(&1));
+    /// assert!(!cache.contains(&2));
     /// ```
     ///
-    pub fn room_left(&self) -> usize {
-        self.store.room_left()
+    pub fn clear(&mut self) {
+        if let Some(on_evict) = &self.on_evict {
+            let now = self.store.now();
+            for item in self.store.drain() {
+                let cause = if item.is_expired(&now) {
+                    RemovalCause::Expired
+                } else {
+                    RemovalCause::Cleared
+                };
+                on_evict.evict(item.k, item.v, cause);
+            }
+        } else {
+            self.store.clear();
+        }
+        {
+            let mut admit = self.admit.lock().unwrap();
+            admit.clear();
+        }
+        {
+            let mut metrics = self.metrics.lock().unwrap();
+            if let Some(metrics) = &mut *metrics {
+                metrics.clear();
+            }
+        }
     }
 
     ///
-    /// Return true if item is in storage
-    ///
-    /// # Arguments
-    ///
-    /// - `k`: item key
+    /// Return cache metrics with actual state of eviction policy
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(!cache.contains(&1));
-    /// assert!(cache.insert(1,1).is_ok());
-    /// assert!(cache.contains(&1));
+    /// let cache = Cache::<u8,u8>::new(100).with_metrics();
+    /// assert!(cache.metrics().is_some());
     /// ```
     ///
-    pub fn contains(&self, k: &K) -> bool {
-        let k = self.key_hash(k);
-        self.store.contains(&k)
+    pub fn metrics(&self) -> Option<Metrics> {
+        let metrics = self.metrics.lock().unwrap();
+        let mut metrics = metrics.clone()?;
+        metrics.set_policy_state(self.store.policy_state());
+        Some(metrics)
     }
 
     ///
-    /// Return item ref if is in cache
-    ///
-    /// # Arguments
-    ///
-    /// - `k`: item key
+    /// An iterator visiting all entries in order. The iterator element type is (&'a K, &'a V). Expired items are skipped.
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert(1,2).is_ok());
-    /// assert_eq!(cache.get(&1), Some(&2));
+    /// let mut cache = Cache::<u8, u8>::new(10);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert!(cache.insert(3, 3).is_ok());
+    /// for (key, val) in cache.iter() {
+    ///     println!("key: {} val: {}", key, val);
+    /// }
     /// ```
     ///
-    pub fn get(&self, k: &K) -> Option<&V> {
-        let k = self.key_hash(k);
-        {
-            let mut admit = self.admit.lock().unwrap();
-            admit.increment(&k);
-        }
-        let result = if let Some(item) = self.store.get(&k) {
-            Some(&item.v)
-        } else {
-            None
-        };
-        let found = result.is_some();
-        {
-            let mut metrics = self.metrics.lock().unwrap();
-            if let Some(metrics) = &mut *metrics {
-                if found {
-                    metrics.insert(MetricType::Hit, &k, 1);
-                } else {
-                    metrics.insert(MetricType::Miss, &k, 1);
-                }
-            }
-        }
-        result
+    pub fn iter(&self) -> Iter<'_, K, V, S> {
+        Iter::new(&self.store)
     }
 
     ///
-    /// Return mutable item ref if is in cache
+    /// An iterator visiting all entries in order, with mutable references to the values.
+    /// The iterator element type is (&'a K, &'a mut V). Expired items are skipped.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// - `k`: item key
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::<u8, u8>::new(10);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// for (_, val) in cache.iter_mut() {
+    ///     *val *= 2;
+    /// }
+    /// assert_eq!(cache.get(&2), Some(&4));
+    /// ```
+    ///
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, S> {
+        let now = self.store.now();
+        IterMut::new(self.store.values_mut(), now)
+    }
+
+    ///
+    /// An iterator visiting all keys in order. The iterator element type is &'a K. Expired items are skipped.
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert(1,1).is_ok());
-    /// let v = cache.get_mut(&1);
-    /// assert!(v.is_some());
-    /// if let Some(v) = v {
-    ///     *v = 2;
-    /// }
-    /// assert_eq!(cache.get(&1), Some(&2));
+    /// let mut cache = Cache::<u8, u8>::new(10);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.keys().collect::<Vec<&u8>>(), vec![&1, &2]);
     /// ```
     ///
-    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
-        let k = self.key_hash(k);
-        {
-            let mut admit = self.admit.lock().unwrap();
-            admit.increment(&k);
-        }
-        let result = if let Some(item) = self.store.get_mut(&k) {
-            Some(&mut item.v)
-        } else {
-            None
-        };
-        let found = result.is_some();
-        {
-            let mut metrics = self.metrics.lock().unwrap();
-            if let Some(metrics) = &mut *metrics {
-                if found {
-                    metrics.insert(MetricType::Hit, &k, 1);
-                } else {
-                    metrics.insert(MetricType::Miss, &k, 1);
-                }
-            }
-        }
-        result
+    pub fn keys(&self) -> Keys<'_, K, V, S> {
+        Keys::new(&self.store)
     }
 
     ///
-    /// Insert item into cache. Item can be rejected (return Err) if  cache is full and estimate of new item is lower than sample item from cache.
-    /// If item is inserted, than preview item value can be returned.
-    /// Cache is cleaned and all expired items are removed before new is inserted.
+    /// An iterator visiting all values in order. The iterator element type is &'a V. Expired items are skipped.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// - `k`: item key
-    /// - `v`: item value
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::<u8, u8>::new(10);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.values().collect::<Vec<&u8>>(), vec![&1, &2]);
+    /// ```
+    ///
+    pub fn values(&self) -> Values<'_, K, V, S> {
+        Values::new(&self.store)
+    }
+
+    ///
+    /// Clears the cache, returning all not expired key-value pairs as an iterator.
+    /// Expired items are removed and passed into `on_evict` before drain.
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert(1,1).is_ok());
-    /// let v = cache.insert(1,2);
-    /// assert!(v.is_ok());
-    /// if let Ok(v) = v {
-    ///      assert_eq!(v, Some(1));
-    /// }
+    /// let mut cache = Cache::<u8, u8>::new(10);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert!(cache.insert(2, 2).is_ok());
+    /// assert_eq!(cache.drain().collect::<Vec<(u8, u8)>>(), vec![(1, 1), (2, 2)]);
+    /// assert!(cache.is_empty());
     /// ```
     ///
-    pub fn insert(&mut self, k: K, v: V) -> Result<Option<V>, Option<()>> {
-        self.insert_with_ttl(k, v, Duration::from_secs(0))
+    pub fn drain(&mut self) -> Drain<'_, K, V, S> {
+        self.store.cleanup(&self.on_evict);
+        let now = self.store.now();
+        Drain::new(self.store.drain(), now)
     }
+}
 
+#[cfg(feature = "serde")]
+impl<K, V, E, S, A, H, W, X> Cache<K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
     ///
-    /// Insert item into cache with defined time to life in seconds.
-    /// Returns preview item if exists with given key.
-    /// Cache is cleaned and all expired items are removed before new is inserted.
+    /// Write snapshot of cache into writer.
     ///
-    /// If expiration time is 0 sec, than item is insert without ttl.
+    /// Snapshot contains key, value, cost, remaining time to live, time to idle and remaining idle time of every not expired item.
+    /// Frequency state of TinyLFU can be saved too, so restored cache gets its admission history back.
     ///
     /// # Arguments
     ///
-    /// - `k`: item key
-    /// - `v`: item value
-    /// - `expiration`: how many seconds should item lives
+    /// - `writer`: destination of snapshot
+    /// - `with_admission`: save also TinyLFU state
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
-    /// use std::time::Duration;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert_with_ttl(1,1, Duration::from_secs(1)).is_ok());
-    /// assert!(cache.contains(&1));
-    /// std::thread::sleep(Duration::from_secs(2));
-    /// assert!(!cache.contains(&1));
+    /// let mut cache = Cache::<u8, String>::new(100);
+    /// assert!(cache.insert(1, "one".to_string()).is_ok());
+    /// let mut snapshot = Vec::new();
+    /// assert!(cache.save_to(&mut snapshot, true).is_ok());
+    ///
+    /// let mut restored = Cache::<u8, String>::new(100);
+    /// assert_eq!(restored.load_from(snapshot.as_slice()).ok(), Some(1));
+    /// assert_eq!(restored.get(&1), Some(&"one".to_string()));
     /// ```
     ///
-    pub fn insert_with_ttl(
-        &mut self,
-        k: K,
-        v: V,
-        expiration: Duration,
-    ) -> Result<Option<V>, Option<()>> {
-        self.store.cleanup(&self.on_evict);
-
-        let key_hash = self.key_hash(&k);
-        let item = Item::new(k, v);
+    pub fn save_to<T>(&self, mut writer: T, with_admission: bool) -> Result<(), SnapshotError>
+    where
+        T: Write,
+        K: Serialize,
+        V: Serialize,
+        H: Serialize,
+        A: Serialize,
+    {
+        let now = self.store.now();
+        let items = self
+            .store
+            .keys()
+            .filter_map(|key_hash| self.store.peek(key_hash))
+            .map(|item| SnapshotItem {
+                k: &item.k,
+                v: &item.v,
+                cost: item.cost,
+                ttl: item
+                    .ttl_expiration_time()
+                    .map(|expiration_time| expiration_time.saturating_duration_since(now)),
+                time_to_idle: item.time_to_idle,
+                idle: item
+                    .time_to_idle
+                    .and(item.expiration_time())
+                    .map(|expiration_time| expiration_time.saturating_duration_since(now)),
+            })
+            .collect();
+        let admit = self.admit.lock().unwrap();
+        let snapshot = Snapshot {
+            saved_at: since_epoch(),
+            items,
+            admission: Some((&self.hasher_builder, &*admit)).filter(|_| with_admission),
+        };
+        bincode::serialize_into(&mut writer, &SNAPSHOT_VERSION)?;
+        bincode::serialize_into(&mut writer, &snapshot)?;
+        Ok(())
+    }
 
-        match self.can_be_insert(&key_hash) {
-            Ok(victim) => {
-                {
-                    let mut admit = self.admit.lock().unwrap();
-                    admit.increment(&key_hash);
-                }
-                self.remove_victim(victim);
-                {
-                    let mut metrics = self.metrics.lock().unwrap();
-                    if let Some(metrics) = &mut *metrics {
-                        metrics.insert(MetricType::KeyInsert, &key_hash, 1);
-                    }
-                }
-                Ok(self.insert_item_with_ttl(key_hash, item, expiration))
+    ///
+    /// Replace contents of cache with snapshot read from reader. Returns number of loaded items.
+    ///
+    /// Current items are removed. Time to live and remaining idle time of items are shortened by time elapsed
+    /// since snapshot was saved and items expired in the meantime are skipped. Items are admitted as by insert, so cache with smaller capacity
+    /// keeps only part of them.
+    ///
+    /// If snapshot contains TinyLFU state, than it replaces TinyLFU state and hasher of cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `reader`: source of snapshot
+    ///
+    pub fn load_from<T>(&mut self, mut reader: T) -> Result<usize, SnapshotError>
+    where
+        T: Read,
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        H: DeserializeOwned,
+        A: DeserializeOwned,
+    {
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: version,
+                supported: SNAPSHOT_VERSION,
+            });
+        }
+        let snapshot: Snapshot<K, V, H, A> = bincode::deserialize_from(&mut reader)?;
+        if let Some((_, admit)) = &snapshot.admission {
+            self.check_window_size(admit.window_size())?;
+        }
+        self.clear();
+        if let Some((hasher_builder, admit)) = snapshot.admission {
+            self.replace_admission(hasher_builder, admit);
+        }
+        let downtime = since_epoch().saturating_sub(snapshot.saved_at);
+        let mut loaded = 0;
+        for item in snapshot.items {
+            let expiration = match item.ttl {
+                Some(ttl) if ttl <= downtime => continue,
+                Some(ttl) => ttl - downtime,
+                None => Duration::from_secs(0),
+            };
+            let idle_remaining = match item.idle {
+                Some(idle) if idle <= downtime => continue,
+                idle => idle.map(|idle| idle - downtime),
+            };
+            let key_hash = self.key_hash(&item.k);
+            let time_to_idle = item.time_to_idle.unwrap_or_default();
+            let mut item =
+                Item::with_cost(item.k, item.v, item.cost).with_time_to_idle(time_to_idle);
+            if let Some(idle_remaining) = idle_remaining {
+                item = item.with_idle_remaining(idle_remaining);
             }
-            Err(victim) => {
-                self.remove_victim(victim);
-                Err(Some(()))
+            if self.insert_hashed(key_hash, item, expiration).is_ok() {
+                loaded += 1;
             }
         }
+        Ok(loaded)
     }
 
     ///
-    /// Remove and return item from cache.
+    /// Write TinyLFU state into writer in versioned binary format.
+    ///
+    /// State is written together with hasher of cache, because TinyLFU counts hashes of keys.
+    /// Items in cache are not written.
     ///
     /// # Arguments
     ///
-    /// - `k`: item key
+    /// - `writer`: destination of admission state
     ///
     /// # Example
     ///
     /// ```
     /// use cascara::Cache;
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert(1,2).is_ok());
-    /// assert!(cache.contains(&1));
-    /// assert_eq!(cache.remove(&1), Some(2));
-    /// assert!(!cache.contains(&1));
+    /// let cache = Cache::<u8, u8>::with_window_size(100, 50);
+    /// assert_eq!(cache.get(&1), None);
+    /// let mut state = Vec::new();
+    /// assert!(cache.export_admission(&mut state).is_ok());
+    ///
+    /// let mut restored = Cache::<u8, u8>::with_window_size(100, 50);
+    /// assert!(restored.import_admission(state.as_slice()).is_ok());
+    /// let mut other = Cache::<u8, u8>::with_window_size(100, 20);
+    /// assert!(other.import_admission(state.as_slice()).is_err());
     /// ```
-    pub fn remove(&mut self, k: &K) -> Option<V> {
-        let k = self.key_hash(k);
-        if let Some(item) = self.store.remove(&k) {
-            Some(item.v)
-        } else {
-            None
-        }
+    ///
+    pub fn export_admission<T>(&self, mut writer: T) -> Result<(), SnapshotError>
+    where
+        T: Write,
+        H: Serialize,
+        A: Serialize,
+    {
+        let admit = self.admit.lock().unwrap();
+        let header = AdmissionHeader {
+            version: ADMISSION_VERSION,
+            window_size: admit.window_size() as u64,
+        };
+        bincode::serialize_into(&mut writer, &header)?;
+        bincode::serialize_into(&mut writer, &(&self.hasher_builder, &*admit))?;
+        Ok(())
     }
 
     ///
-    /// Remove all items from cache.
+    /// Replace TinyLFU state and hasher of cache with state read from reader.
     ///
-    /// # Example
+    /// Saved window size has to be same as window size of cache.
+    /// Current items are removed, because their key hashes are not valid with restored hasher.
     ///
-    /// ```
-    /// use cascara::Cache;
+    /// # Arguments
     ///
-    /// let mut cache = Cache::new(100);
-    /// assert!(cache.insert(1,1).is_ok());
-    /// assert!(cache.insert(2,2).is_ok());
-    /// assert!(cache.contains(&1));
-    /// assert!(cache.contains(&2));
-    /// cache.clear();
-    /// assert!(!cache.contains(&1));
-    /// assert!(!cache.contains(&2));
-    /// ```
+    /// - `reader`: source of admission state
+    ///
+    pub fn import_admission<T>(&mut self, mut reader: T) -> Result<(), SnapshotError>
+    where
+        T: Read,
+        H: DeserializeOwned,
+        A: DeserializeOwned,
+    {
+        let header: AdmissionHeader = bincode::deserialize_from(&mut reader)?;
+        if header.version != ADMISSION_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: header.version,
+                supported: ADMISSION_VERSION,
+            });
+        }
+        self.check_window_size(header.window_size as usize)?;
+        let (hasher_builder, admit): (H, A) = bincode::deserialize_from(&mut reader)?;
+        self.check_window_size(admit.window_size())?;
+        self.clear();
+        self.replace_admission(hasher_builder, admit);
+        Ok(())
+    }
+
     ///
-    pub fn clear(&mut self) {
-        self.store.clear();
-        {
-            let mut admit = self.admit.lock().unwrap();
-            admit.clear();
+    /// Check if saved window size is same as window size of cache TinyLFU
+    ///
+    fn check_window_size(&self, found: usize) -> Result<(), SnapshotError> {
+        let expected = self.admit.lock().unwrap().window_size();
+        if found == expected {
+            Ok(())
+        } else {
+            Err(SnapshotError::WindowSizeMismatch { found, expected })
         }
-        {
-            let mut metrics = self.metrics.lock().unwrap();
-            if let Some(metrics) = &mut *metrics {
-                metrics.clear();
-            }
+    }
+
+    fn replace_admission(&mut self, hasher_builder: H, admit: A) {
+        if let Some(climber) = &mut self.climber {
+            climber.get_mut().unwrap().reset(admit.window_size());
         }
+        self.hasher_builder = hasher_builder;
+        *self.admit.lock().unwrap() = admit;
+    }
+}
+
+impl<K, V, E, S, A, H, W, X> IntoIterator for Cache<K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let now = self.store.now();
+        IntoIter::new(self.store.into_values(), now)
     }
+}
+
+impl<'a, K, V, E, S, A, H, W, X> IntoIterator for &'a Cache<K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, S>;
 
-    ///
-    /// Return cache metrics
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use cascara::Cache;
-    ///
-    /// let cache = Cache::<u8,u8>::new(100).with_metrics();
-    /// assert!(cache.metrics().is_some());
-    /// ```
-    ///
-    pub fn metrics(&self) -> Option<Metrics> {
-        let metrics = self.metrics.lock().unwrap();
-        if let Some(metrics) = &*metrics {
-            Some(metrics.clone())
-        } else {
-            None
-        }
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
+}
 
-    ///
-    /// An iterator visiting all entries in order. The iterator element type is (&'a K, &'a V).
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use cascara::Cache;
-    ///
-    /// let mut cache = Cache::<u8, u8>::new(10);
-    /// assert!(cache.insert(1, 1).is_ok());
-    /// assert!(cache.insert(2, 2).is_ok());
-    /// assert!(cache.insert(3, 3).is_ok());
-    /// for (key, val) in cache.iter() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    ///
-    pub fn iter(&self) -> Iter<K, V, S> {
-        Iter::new(&self.store)
+impl<'a, K, V, E, S, A, H, W, X> IntoIterator for &'a mut Cache<K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cache::{Cache, OnEvict};
-    use crate::tiny_lfu::TinyLFU;
+    use crate::admission::AdmissionDecision;
+    use crate::cache::{Cache, OnEvict, RemovalCause, UnitWeigher, VoidEvict, MAX_VICTIMS};
+    use crate::clock::ManualClock;
+    use crate::error::InsertError;
+    #[cfg(feature = "serde")]
+    use crate::error::SnapshotError;
+    use crate::expiry::{Expiry, VoidExpiry};
+    use crate::policy::SampledPolicy;
+    use crate::store::Storage;
+    use crate::tiny_lfu::{TinyLFU, TinyLFUCache, TinyLFUConfig};
+    use probabilistic_collections::SipHasherBuilder;
     use std::fmt::Debug;
+    use std::hash::{BuildHasherDefault, Hasher};
+    use std::marker::PhantomData;
+    use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
+    #[derive(Default)]
+    struct CollisionHasher {}
+
+    impl Hasher for CollisionHasher {
+        fn finish(&self) -> u64 {
+            42
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    type CollisionCache = Cache<
+        u64,
+        u64,
+        VoidEvict<u64, u64>,
+        Storage<u64, u64>,
+        TinyLFUCache,
+        BuildHasherDefault<CollisionHasher>,
+    >;
+
+    fn collision_cache(capacity: usize) -> CollisionCache {
+        Cache {
+            hasher_builder: BuildHasherDefault::default(),
+            store: Storage::with_capacity(capacity),
+            admit: Arc::new(Mutex::new(TinyLFUCache::new(16))),
+            on_evict: None,
+            weigher: UnitWeigher,
+            expiry: None,
+            default_ttl: Duration::from_secs(0),
+            metrics: Mutex::new(None),
+            admission_log: None,
+            climber: None,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+        .with_metrics()
+    }
+
     #[test]
     fn estimate() {
         let mut cache = Cache::new(10).with_metrics();
@@ -691,6 +2384,63 @@ mod tests {
     }
 
     #[test]
+    fn window_size() {
+        let cache = Cache::<u64, u64>::new(2_000);
+        assert_eq!(cache.admit.lock().unwrap().window_size(), 20_000);
+        let cache = Cache::<u64, u64>::with_window_size(10, 50_000);
+        assert_eq!(cache.admit.lock().unwrap().window_size(), 50_000);
+    }
+
+    #[test]
+    fn compact_sketch() {
+        let mut cache = Cache::with_compact_sketch(2, TinyLFUConfig::default()).with_metrics();
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        for _ in 0..3 {
+            assert_eq!(cache.get(&1), Some(&1));
+            assert_eq!(cache.get(&2), Some(&2));
+        }
+        //new item with lower frequency is rejected
+        assert!(cache.insert(3, 3).is_err());
+        assert!(!cache.contains(&3));
+        assert_eq!(cache.admit.lock().unwrap().estimate(&cache.key_hash(&1)), 4);
+    }
+
+    #[test]
+    fn admission_introspection() {
+        let mut cache = Cache::new(2).with_admission_log(10, 1);
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        for _ in 0..3 {
+            assert_eq!(cache.get(&1), Some(&1));
+            assert_eq!(cache.get(&2), Some(&2));
+        }
+        assert_eq!(cache.frequency(&1), 4);
+        assert_eq!(cache.frequency(&3), 0);
+        match cache.would_admit(&3) {
+            AdmissionDecision::Rejected {
+                incoming_estimate,
+                victim,
+                victim_estimate,
+            } => {
+                assert_eq!(incoming_estimate, 0);
+                assert_eq!(victim_estimate, 4);
+                assert!(victim.is_some());
+            }
+            _ => panic!("Item should be rejected"),
+        }
+        //explain does not change cache
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.frequency(&3), 0);
+        assert!(cache.insert(3, 3).is_err());
+        let records = cache.admission_log();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].key_hash, cache.key_hash(&3));
+        assert!(!records[2].decision.is_admitted());
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn insert() {
         let mut cache = Cache::new(2).with_metrics();
         if let Ok(preview) = cache.insert(1, 1) {
@@ -702,6 +2452,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn insert_with_ttl() {
         let mut cache = Cache::new(2).with_metrics();
         if let Ok(preview) = cache.insert_with_ttl(1, 1, Duration::from_secs(1)) {
@@ -714,37 +2465,379 @@ mod tests {
 
     #[test]
     fn cleanup_before_insert() {
-        let mut cache = Cache::new(2).with_metrics();
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(2, clock.clone()).with_metrics();
         assert!(cache.insert_with_ttl(1, 1, Duration::from_secs(1)).is_ok());
         assert!(cache.contains(&1));
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
+        assert!(cache.insert(2, 2).is_ok());
+        assert!(!cache.contains(&1));
+        assert!(cache.contains(&2));
+    }
+
+    #[test]
+    fn sub_second_ttl() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(2, clock.clone());
+        assert!(cache
+            .insert_with_ttl(1, 1, Duration::from_millis(500))
+            .is_ok());
+        clock.advance(Duration::from_millis(400));
+        assert!(cache.contains(&1));
+        clock.advance(Duration::from_millis(200));
+        assert!(!cache.contains(&1));
         assert!(cache.insert(2, 2).is_ok());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn time_to_idle() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(2, clock.clone());
+        assert!(cache.insert_with_tti(1, 1, Duration::from_secs(1)).is_ok());
+        clock.advance(Duration::from_millis(800));
+        assert_eq!(cache.get(&1), Some(&1));
+        clock.advance(Duration::from_millis(800));
+        assert_eq!(cache.get_mut(&1), Some(&mut 1));
+        clock.advance(Duration::from_millis(800));
+        assert!(cache.contains(&1));
+        clock.advance(Duration::from_millis(300));
         assert!(!cache.contains(&1));
+        assert!(cache.insert(2, 2).is_ok());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn time_to_idle_capped_by_ttl() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(2, clock.clone());
+        assert!(cache
+            .insert_with_ttl_and_tti(1, 1, Duration::from_secs(3), Duration::from_secs(1))
+            .is_ok());
+        for _ in 0..3 {
+            clock.advance(Duration::from_millis(900));
+            assert_eq!(cache.get(&1), Some(&1));
+        }
+        clock.advance(Duration::from_millis(400));
+        assert_eq!(cache.get(&1), None);
+    }
+
+    struct ValueExpiry;
+
+    impl Expiry<u64, u64> for ValueExpiry {
+        fn expire_after_create(&self, _k: &u64, v: &u64) -> Duration {
+            Duration::from_secs(*v)
+        }
+
+        fn expire_after_read(&self, _k: &u64, v: &u64, remaining: Duration) -> Duration {
+            if *v == 1 {
+                Duration::from_secs(1)
+            } else {
+                remaining
+            }
+        }
+    }
+
+    #[test]
+    fn expiry() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(10, clock.clone()).with_expiry(ValueExpiry);
+        assert!(cache.insert(0, 0).is_ok());
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        assert!(cache.insert_with_ttl(3, 2, Duration::from_secs(5)).is_ok());
+        clock.advance(Duration::from_millis(800));
+        assert_eq!(cache.get(&1), Some(&1));
+        assert_eq!(cache.get(&2), Some(&2));
+        clock.advance(Duration::from_millis(800));
+        assert!(cache.contains(&1));
         assert!(cache.contains(&2));
+        clock.advance(Duration::from_millis(800));
+        assert!(cache.contains(&0));
+        assert!(!cache.contains(&1));
+        assert!(!cache.contains(&2));
+        assert!(cache.contains(&3));
+    }
+
+    #[test]
+    fn expiry_after_update() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(10, clock.clone()).with_expiry(ValueExpiry);
+        assert!(cache.insert(1, 2).is_ok());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.insert(1, 5), Ok(Some(2)));
+        clock.advance(Duration::from_millis(1100));
+        assert!(!cache.contains(&1));
+    }
+
+    #[test]
+    fn expiry_at_expiration_time() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(10, clock.clone()).with_expiry(ValueExpiry);
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        clock.advance(Duration::from_secs(1));
+        //read of item at its expiration time does not extend it
+        assert_eq!(cache.get(&1), Some(&1));
+        clock.advance(Duration::from_nanos(1));
+        assert!(!cache.contains(&1));
+        //item updated at its expiration time is created again instead of keeping zero remaining time
+        clock.advance(Duration::from_secs(1) - Duration::from_nanos(1));
+        assert!(cache.insert(2, 3).is_ok());
+        clock.advance(Duration::from_millis(3100));
+        assert!(!cache.contains(&2));
     }
 
     #[derive(Default, Debug)]
     struct TestEvict {}
 
     impl OnEvict<usize, usize> for TestEvict {
-        fn evict(&self, k: &usize, v: &usize) {
-            assert_eq!(k, &1);
-            assert_eq!(v, &2);
+        fn evict(&self, k: usize, v: usize, _cause: RemovalCause) {
+            assert_eq!(k, 1);
+            assert_eq!(v, 2);
         }
     }
 
     #[test]
     fn cleanup_with_evict() {
-        let mut cache = Cache::with_on_evict(2, TestEvict::default()).with_metrics();
+        let clock = ManualClock::new();
+        let mut cache = Cache::<_, _, _, _, _, _, _, VoidExpiry>::from_parts(
+            Storage::with_clock(2, SampledPolicy, clock.clone()),
+            Arc::new(Mutex::new(TinyLFUCache::new(10))),
+            Some(TestEvict::default()),
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        )
+        .with_metrics();
         assert!(cache.insert_with_ttl(1, 2, Duration::from_secs(1)).is_ok());
         assert!(cache.contains(&1));
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
         assert!(cache.insert(2, 2).is_ok());
         assert!(!cache.contains(&1));
         assert!(cache.contains(&2));
     }
 
+    type Removed = Arc<Mutex<Vec<(u64, u64, RemovalCause)>>>;
+
+    struct RecordEvict {
+        removed: Removed,
+    }
+
+    impl OnEvict<u64, u64> for RecordEvict {
+        fn evict(&self, k: u64, v: u64, cause: RemovalCause) {
+            self.removed.lock().unwrap().push((k, v, cause));
+        }
+    }
+
+    struct Value(u64);
+
+    struct SumEvict {
+        sum: Arc<Mutex<u64>>,
+    }
+
+    impl OnEvict<u64, Value> for SumEvict {
+        fn evict(&self, _k: u64, v: Value, _cause: RemovalCause) {
+            *self.sum.lock().unwrap() += v.0;
+        }
+    }
+
+    #[test]
+    fn on_evict_without_clone() {
+        let sum = Arc::new(Mutex::new(0));
+        let mut cache = Cache::with_on_evict(10, SumEvict { sum: sum.clone() });
+        assert!(cache.insert(1, Value(1)).is_ok());
+        assert!(matches!(cache.insert(1, Value(2)), Ok(None)));
+        assert!(cache.remove(&1).is_none());
+        assert_eq!(*sum.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn removal_cause() {
+        let removed = Removed::default();
+        let clock = ManualClock::new();
+        let mut cache = Cache::<_, _, _, _, _, _, _, VoidExpiry>::from_parts(
+            Storage::with_clock(2, SampledPolicy, clock.clone()),
+            Arc::new(Mutex::new(TinyLFUCache::new(10))),
+            Some(RecordEvict {
+                removed: removed.clone(),
+            }),
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        );
+        assert!(cache.insert_with_ttl(1, 1, Duration::from_secs(1)).is_ok());
+        clock.advance(Duration::from_secs(2));
+        cache.run_pending_tasks();
+        assert!(cache.insert(2, 2).is_ok());
+        //replaced and removed values are moved to on_evict
+        assert_eq!(cache.insert(2, 3), Ok(None));
+        assert!(cache.invalidate(&2));
+        assert!(!cache.invalidate(&2));
+        assert!(cache.insert(3, 3).is_ok());
+        assert!(cache.insert(4, 4).is_ok());
+        assert_eq!(cache.remove(&4), None);
+        cache.clear();
+        assert_eq!(
+            *removed.lock().unwrap(),
+            vec![
+                (1, 1, RemovalCause::Expired),
+                (2, 2, RemovalCause::Replaced),
+                (2, 3, RemovalCause::Explicit),
+                (4, 4, RemovalCause::Explicit),
+                (3, 3, RemovalCause::Cleared)
+            ]
+        );
+    }
+
+    #[test]
+    fn removal_cause_size_and_replaced() {
+        let removed = Removed::default();
+        let mut cache = Cache::<_, _, _, _, _, _, _, VoidExpiry>::from_parts(
+            Storage::with_capacity(1),
+            Arc::new(Mutex::new(TinyLFUCache::new(10))),
+            Some(RecordEvict {
+                removed: removed.clone(),
+            }),
+            BuildHasherDefault::<CollisionHasher>::default(),
+            UnitWeigher,
+        );
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        assert_eq!(
+            removed.lock().unwrap().pop(),
+            Some((1, 1, RemovalCause::Replaced))
+        );
+
+        let mut cache = Cache::<_, _, _, _, _, _, _, VoidExpiry>::from_parts(
+            Storage::with_capacity(1),
+            Arc::new(Mutex::new(TinyLFUCache::new(10))),
+            Some(RecordEvict {
+                removed: removed.clone(),
+            }),
+            SipHasherBuilder::from_entropy(),
+            UnitWeigher,
+        );
+        assert!(cache.insert(1, 1).is_ok());
+        let k = cache.key_hash(&2);
+        cache.admit.lock().unwrap().increment(&k);
+        cache.admit.lock().unwrap().increment(&k);
+        assert!(cache.insert(2, 2).is_ok());
+        assert_eq!(
+            removed.lock().unwrap().pop(),
+            Some((1, 1, RemovalCause::Size))
+        );
+        assert!(RemovalCause::Size.was_evicted());
+        assert!(!RemovalCause::Replaced.was_evicted());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(10, clock.clone());
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert_with_ttl(2, 2, Duration::from_secs(10)).is_ok());
+        assert!(cache.insert_with_ttl(3, 3, Duration::from_secs(1)).is_ok());
+        assert!(cache.insert_with_tti(4, 4, Duration::from_secs(5)).is_ok());
+        assert_eq!(cache.get(&1), Some(&1));
+        clock.advance(Duration::from_secs(4));
+        let mut snapshot = Vec::new();
+        assert!(cache.save_to(&mut snapshot, true).is_ok());
+
+        let restored_clock = ManualClock::new();
+        let mut restored = Cache::with_clock(10, restored_clock.clone());
+        assert!(restored.insert(5, 5).is_ok());
+        assert_eq!(restored.load_from(snapshot.as_slice()).ok(), Some(3));
+        assert!(!restored.contains(&5));
+        assert_eq!(restored.len(), 3);
+        let k = restored.key_hash(&1);
+        assert_eq!(k, cache.key_hash(&1));
+        assert_eq!(
+            restored.admit.lock().unwrap().estimate(&k),
+            cache.admit.lock().unwrap().estimate(&k)
+        );
+        restored_clock.advance(Duration::from_millis(5500));
+        assert!(restored.contains(&1));
+        assert!(restored.contains(&2));
+        assert!(!restored.contains(&4));
+        restored_clock.advance(Duration::from_secs(1));
+        assert!(!restored.contains(&2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_without_admission() {
+        let mut cache = Cache::<u64, u64>::new(10);
+        assert!(cache.insert(1, 1).is_ok());
+        let mut snapshot = Vec::new();
+        assert!(cache.save_to(&mut snapshot, false).is_ok());
+        let mut restored = Cache::<u64, u64>::new(10);
+        assert_eq!(restored.load_from(snapshot.as_slice()).ok(), Some(1));
+        assert_eq!(restored.get(&1), Some(&1));
+
+        snapshot[0] = 2;
+        assert!(matches!(
+            restored.load_from(snapshot.as_slice()),
+            Err(SnapshotError::UnsupportedVersion { found: 2, .. })
+        ));
+        assert!(restored.contains(&1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_idle_deadline() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::<u64, u64, _, _>::with_clock(10, clock.clone());
+        assert!(cache.insert_with_tti(1, 1, Duration::from_secs(5)).is_ok());
+        clock.advance(Duration::from_secs(4));
+        let mut snapshot = Vec::new();
+        assert!(cache.save_to(&mut snapshot, false).is_ok());
+
+        let restored_clock = ManualClock::new();
+        let mut restored = Cache::<u64, u64, _, _>::with_clock(10, restored_clock.clone());
+        assert_eq!(restored.load_from(snapshot.as_slice()).ok(), Some(1));
+        //almost idle item keeps its idle deadline, next access moves it by full time to idle
+        restored_clock.advance(Duration::from_millis(500));
+        assert_eq!(restored.get(&1), Some(&1));
+        restored_clock.advance(Duration::from_secs(4));
+        assert!(restored.contains(&1));
+        restored_clock.advance(Duration::from_millis(1100));
+        assert!(!restored.contains(&1));
+
+        let mut restored = Cache::<u64, u64, _, _>::with_clock(10, restored_clock.clone());
+        assert_eq!(restored.load_from(snapshot.as_slice()).ok(), Some(1));
+        restored_clock.advance(Duration::from_millis(1100));
+        assert!(!restored.contains(&1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn admission_state() {
+        let cache = Cache::<u64, u64>::with_window_size(10, 100);
+        for _ in 0..5 {
+            assert_eq!(cache.get(&1), None);
+        }
+        let mut state = Vec::new();
+        assert!(cache.export_admission(&mut state).is_ok());
+
+        let mut restored = Cache::<u64, u64>::with_window_size(10, 100);
+        assert!(restored.insert(2, 2).is_ok());
+        assert!(restored.import_admission(state.as_slice()).is_ok());
+        assert!(restored.is_empty());
+        let k = restored.key_hash(&1);
+        assert_eq!(restored.admit.lock().unwrap().estimate(&k), 5);
+
+        let mut other = Cache::<u64, u64>::with_window_size(10, 50);
+        assert!(matches!(
+            other.import_admission(state.as_slice()),
+            Err(SnapshotError::WindowSizeMismatch {
+                found: 100,
+                expected: 50
+            })
+        ));
+    }
+
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn update() {
         let mut cache = Cache::new(2).with_metrics();
         assert!(cache.insert(1, 1).is_ok());
@@ -775,9 +2868,9 @@ mod tests {
         assert!(cache.insert(1, 2).is_ok());
         assert!(cache.insert(2, 2).is_ok());
         let k = cache.key_hash(&2);
-        cache.admit.get_mut().unwrap().increment(&k);
+        cache.admit.lock().unwrap().increment(&k);
         let k = cache.key_hash(&3);
-        cache.admit.get_mut().unwrap().increment(&k);
+        cache.admit.lock().unwrap().increment(&k);
         assert!(cache.insert(3, 3).is_ok());
         assert!(cache.contains(&2));
         assert!(cache.contains(&3));
@@ -790,12 +2883,20 @@ mod tests {
         assert!(cache.insert(1, 1).is_ok());
         assert!(cache.insert(2, 2).is_ok());
         let k = cache.key_hash(&1);
-        cache.admit.get_mut().unwrap().increment(&k);
-        if let Err(_) = cache.insert(4, 4) {
-            assert!(cache.contains(&1));
-            assert!(!cache.contains(&2), "Victim should be value 2");
-        } else {
-            assert!(false, "Item should be reject because of low estimate");
+        cache.admit.lock().unwrap().increment(&k);
+        match cache.insert(4, 4) {
+            Err(InsertError::Rejected {
+                k,
+                v,
+                incoming_estimate,
+                victim_estimate,
+            }) => {
+                assert_eq!((k, v), (4, 4));
+                assert!(incoming_estimate < victim_estimate);
+                assert!(cache.contains(&1));
+                assert!(!cache.contains(&2), "Victim should be value 2");
+            }
+            _ => panic!("Item should be reject because of low estimate"),
         }
     }
 
@@ -856,4 +2957,228 @@ mod tests {
         cache.clear();
         assert!(cache.is_empty());
     }
+
+    #[test]
+    fn collision_get() {
+        let mut cache = collision_cache(10);
+        assert!(cache.insert(1, 1).is_ok());
+        assert_eq!(cache.get(&1), Some(&1));
+        assert_eq!(cache.get(&2), None);
+        assert!(cache.get_mut(&2).is_none());
+        assert!(cache.contains(&1));
+        assert!(!cache.contains(&2));
+    }
+
+    #[test]
+    fn collision_remove() {
+        let mut cache = collision_cache(10);
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.remove(&2).is_none());
+        assert!(cache.contains(&1));
+        assert_eq!(cache.remove(&1), Some(1));
+    }
+
+    #[test]
+    fn collision_insert() {
+        let mut cache = collision_cache(10);
+        assert!(cache.insert(1, 1).is_ok());
+        assert_eq!(cache.insert(2, 2), Ok(None));
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.get(&2), Some(&2));
+        assert_eq!(cache.insert(2, 3), Ok(Some(2)));
+        let metrics = cache.metrics().unwrap();
+        assert_eq!(metrics.keys_evicted(), 1);
+        assert_eq!(metrics.keys_updated(), 1);
+    }
+
+    #[test]
+    fn insert_with_cost() {
+        let mut cache = Cache::new(10).with_metrics();
+        assert!(cache.insert_with_cost(1, 1, 4).is_ok());
+        assert!(cache.insert_with_cost(2, 2, 4).is_ok());
+        assert_eq!(cache.room_left(), 2);
+        assert!(cache.insert_with_cost(3, 3, 11).is_err());
+        assert_eq!(cache.insert_with_cost(1, 2, 6), Ok(Some(1)));
+        assert_eq!(cache.room_left(), 0);
+    }
+
+    #[test]
+    fn insert_with_cost_evicts_more_victims() {
+        let mut cache = Cache::new(10).with_metrics();
+        for i in 0..5 {
+            assert!(cache.insert_with_cost(i, i, 2).is_ok());
+        }
+        let k = cache.key_hash(&10);
+        for _ in 0..10 {
+            cache.admit.lock().unwrap().increment(&k);
+        }
+        assert!(cache.insert_with_cost(10, 10, 5).is_ok());
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.room_left(), 1);
+        assert_eq!(cache.metrics().unwrap().keys_evicted(), 3);
+    }
+
+    #[test]
+    fn insert_with_cost_samples_limited_victims() {
+        let mut cache = Cache::new(100);
+        for i in 0..100 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!(matches!(
+            cache.insert_with_cost(1000, 1000, 100),
+            Err(InsertError::NoRoom {
+                room: MAX_VICTIMS,
+                ..
+            })
+        ));
+        assert_eq!(cache.len(), 100);
+    }
+
+    #[test]
+    fn reject_insert_with_cost() {
+        let mut cache = Cache::new(4).with_metrics();
+        for i in 0..4 {
+            assert!(cache.insert(i, i).is_ok());
+            let k = cache.key_hash(&i);
+            cache.admit.lock().unwrap().increment(&k);
+        }
+        assert!(cache.insert_with_cost(10, 10, 3).is_err());
+        assert!(!cache.contains(&10));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn reject_too_costly() {
+        let mut cache = Cache::new(4);
+        let err = cache.insert_with_cost(1, 1, 5).unwrap_err();
+        assert_eq!(
+            err,
+            InsertError::TooCostly {
+                k: 1,
+                v: 1,
+                cost: 5,
+                capacity: 4
+            }
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn weigher() {
+        let mut cache = Cache::new(10).with_weigher(|_k: &u8, v: &Vec<u8>| v.len());
+        assert!(cache.insert(1, vec![1, 2, 3]).is_ok());
+        assert_eq!(cache.room_left(), 7);
+        assert!(cache.insert(2, vec![0; 20]).is_err());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn classic_policies() {
+        let mut cache = Cache::with_lru(3).with_metrics();
+        for i in 0..3 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!(cache.get(&0).is_some());
+        assert!(cache.insert(3, 3).is_ok());
+        assert!(cache.contains(&0));
+        assert!(!cache.contains(&1));
+
+        let mut cache = Cache::with_lfu(3);
+        for i in 0..3 {
+            assert!(cache.insert(i, i).is_ok());
+            for _ in 0..3 - i {
+                assert!(cache.get(&i).is_some());
+            }
+        }
+        //new items are always admitted and replace each other as the least frequently used
+        for i in 10..20 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!(cache.contains(&0));
+        assert!(cache.contains(&1));
+        assert!(cache.contains(&19));
+
+        let mut cache = Cache::with_fifo(3);
+        for i in 0..3 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!(cache.get(&0).is_some());
+        assert!(cache.insert(0, 10).is_ok());
+        assert!(cache.insert(3, 3).is_ok());
+        assert!(!cache.contains(&0));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn adaptive_policies() {
+        let mut cache = Cache::with_arc(4).with_metrics();
+        for i in 0..4 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!(cache.get(&0).is_some());
+        assert!(cache.insert(4, 4).is_ok());
+        assert!(cache.contains(&0));
+        assert!(!cache.contains(&1));
+        //evicted item comes back from ghost list and grows recency list
+        assert!(cache.insert(1, 1).is_ok());
+        let metrics = cache.metrics().unwrap();
+        assert_eq!(metrics.policy_state("arc_p"), Some(1));
+        assert_eq!(metrics.policy_state("arc_t2"), Some(2));
+
+        let mut cache = Cache::with_lirs(10).with_metrics();
+        for i in 0..9 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        //scan passes through HIR queue and does not flush LIR items
+        for i in 100..200 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!((0..9).all(|i| cache.contains(&i)));
+        let metrics = cache.metrics().unwrap();
+        assert_eq!(metrics.policy_state("lirs_lir"), Some(9));
+        assert_eq!(metrics.policy_state("lirs_hir"), Some(1));
+
+        let mut cache = Cache::with_s3_fifo(10).with_metrics();
+        for i in 0..9 {
+            assert!(cache.insert(i, i).is_ok());
+            assert!(cache.get(&i).is_some());
+        }
+        for i in 100..200 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert!((0..9).all(|i| cache.contains(&i)));
+        let metrics = cache.metrics().unwrap();
+        assert_eq!(metrics.policy_state("s3_fifo_main"), Some(9));
+        assert_eq!(metrics.policy_state("s3_fifo_small"), Some(1));
+    }
+
+    #[test]
+    fn admission_window() {
+        let mut cache = Cache::with_admission_window(3).with_metrics();
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        assert!(cache.insert(3, 3).is_ok());
+        for _ in 0..5 {
+            assert!(cache.get(&1).is_some());
+            assert!(cache.get(&2).is_some());
+        }
+        //new keys are always admitted and replace each other in window
+        for i in 10..20 {
+            assert!(cache.insert(i, i).is_ok());
+        }
+        assert_eq!(cache.len(), 3);
+        assert!(cache.contains(&1));
+        assert!(cache.contains(&2));
+        assert!(cache.contains(&19));
+    }
+
+    #[test]
+    fn entry_single_increment() {
+        let mut cache = Cache::new(10);
+        let key_hash = cache.key_hash(&1);
+        assert!(cache.entry(1).or_insert(1).is_ok());
+        assert_eq!(cache.admit.lock().unwrap().estimate(&key_hash), 1);
+        assert!(cache.entry(1).or_insert(2).is_ok());
+        assert_eq!(cache.admit.lock().unwrap().estimate(&key_hash), 2);
+    }
 }
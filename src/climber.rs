@@ -0,0 +1,199 @@
+use crate::metrics::Metrics;
+use crate::tiny_lfu::TinyLFU;
+use std::sync::Mutex;
+
+///
+/// Initial step of window size as fraction of initial window size
+///
+const INITIAL_STEP: f64 = 0.0625;
+
+///
+/// Decay of step after every adjustment
+///
+const STEP_DECAY: f64 = 0.98;
+
+///
+/// Change of hit ratio which restarts step to its initial size, because workload has changed
+///
+const RESTART_THRESHOLD: f64 = 0.05;
+
+///
+/// How many times can window grow or shrink from its initial size
+///
+const MAX_SCALE: usize = 4;
+
+///
+/// Adapt window size of TinyLFU by hill climbing over hit ratio.
+///
+/// Hit ratio is sampled from metrics every period of lookups.
+/// Window moves in the same direction while hit ratio improves and turns back when hit ratio drops.
+/// Step decays over time, so window settles down, and restarts when hit ratio changes a lot.
+///
+#[derive(Debug)]
+pub struct HillClimber {
+    period: usize,
+    lookups: usize,
+    hits: usize,
+    misses: usize,
+    previous_ratio: Option<f64>,
+    initial_step: f64,
+    step: f64,
+    increase: bool,
+    min_window: usize,
+    max_window: usize,
+}
+
+impl HillClimber {
+    ///
+    /// Create new climber
+    ///
+    /// # Arguments
+    ///
+    /// - `period`: number of lookups between two samples of hit ratio
+    /// - `window_size`: initial window size of TinyLFU
+    ///
+    /// # Panic
+    ///
+    /// If `period` is 0.
+    ///
+    pub fn new(period: usize, window_size: usize) -> Self {
+        assert_ne!(period, 0);
+        let initial_step = (window_size as f64 * INITIAL_STEP).max(1.0);
+        Self {
+            period,
+            lookups: 0,
+            hits: 0,
+            misses: 0,
+            previous_ratio: None,
+            initial_step,
+            step: initial_step,
+            increase: true,
+            min_window: (window_size / MAX_SCALE).max(1),
+            max_window: window_size.saturating_mul(MAX_SCALE),
+        }
+    }
+
+    ///
+    /// Start climbing again from replaced window size, so its bounds are derived from it
+    ///
+    /// # Arguments
+    ///
+    /// - `window_size`: new window size of TinyLFU
+    ///
+    #[cfg(feature = "serde")]
+    pub fn reset(&mut self, window_size: usize) {
+        *self = Self {
+            hits: self.hits,
+            misses: self.misses,
+            ..Self::new(self.period, window_size)
+        };
+    }
+
+    ///
+    /// Count lookup and adjust window size of TinyLFU at the end of period
+    ///
+    /// # Arguments
+    ///
+    /// - `metrics`: metrics of cache with hits and misses
+    /// - `admit`: TinyLFU with adapted window size
+    ///
+    pub fn record<A: TinyLFU>(&mut self, metrics: &Metrics, admit: &Mutex<A>) {
+        self.lookups += 1;
+        if self.lookups < self.period {
+            return;
+        }
+        self.lookups = 0;
+        let (hits, misses) = (metrics.hits(), metrics.misses());
+        let sampled_hits = hits.saturating_sub(self.hits);
+        let sampled_misses = misses.saturating_sub(self.misses);
+        self.hits = hits;
+        self.misses = misses;
+        if sampled_hits + sampled_misses == 0 {
+            return;
+        }
+        let ratio = sampled_hits as f64 / (sampled_hits + sampled_misses) as f64;
+        let mut admit = admit.lock().unwrap();
+        let window_size = self.adjust(ratio, admit.window_size());
+        admit.set_window_size(window_size);
+    }
+
+    ///
+    /// Returns new window size for sampled hit ratio
+    ///
+    fn adjust(&mut self, ratio: f64, window_size: usize) -> usize {
+        if let Some(previous_ratio) = self.previous_ratio {
+            let change = ratio - previous_ratio;
+            if change < 0.0 {
+                self.increase = !self.increase;
+            }
+            if change.abs() >= RESTART_THRESHOLD {
+                self.step = self.initial_step;
+            } else {
+                self.step = (self.step * STEP_DECAY).max(1.0);
+            }
+        }
+        self.previous_ratio = Some(ratio);
+        let step = self.step as usize;
+        let window_size = if self.increase {
+            window_size.saturating_add(step)
+        } else {
+            window_size.saturating_sub(step)
+        };
+        window_size.clamp(self.min_window, self.max_window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::climber::HillClimber;
+    use crate::metrics::{MetricType, Metrics};
+    use crate::tiny_lfu::{TinyLFU, TinyLFUCache};
+    use std::sync::Mutex;
+
+    #[test]
+    fn climb() {
+        let mut climber = HillClimber::new(10, 160);
+        assert_eq!(climber.adjust(0.5, 160), 170);
+        //hit ratio improves, so window keeps growing
+        assert_eq!(climber.adjust(0.52, 170), 179);
+        //hit ratio drops, so window turns back
+        assert_eq!(climber.adjust(0.51, 179), 170);
+        //big change of hit ratio restarts step
+        assert_eq!(climber.adjust(0.3, 170), 180);
+    }
+
+    #[test]
+    fn bounds() {
+        let mut climber = HillClimber::new(10, 16);
+        for _ in 0..100 {
+            climber.adjust(0.5, 16);
+        }
+        assert_eq!(climber.adjust(0.5, 64), 64);
+        assert_eq!(climber.adjust(0.4, 4), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn reset() {
+        let mut climber = HillClimber::new(10, 16);
+        climber.reset(160);
+        for _ in 0..100 {
+            climber.adjust(0.5, 160);
+        }
+        assert_eq!(climber.adjust(0.5, 640), 640);
+        assert_eq!(climber.adjust(0.4, 40), 40);
+    }
+
+    #[test]
+    fn record() {
+        let admit = Mutex::new(TinyLFUCache::new(160));
+        let mut metrics = Metrics::new();
+        let mut climber = HillClimber::new(2, 160);
+        metrics.insert(MetricType::Hit, &1, 1);
+        climber.record(&metrics, &admit);
+        assert_eq!(admit.lock().unwrap().window_size(), 160);
+        metrics.insert(MetricType::Miss, &1, 1);
+        climber.record(&metrics, &admit);
+        assert_eq!(admit.lock().unwrap().window_size(), 170);
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+///
+/// Source of current time for expiration of items
+///
+pub trait Clock {
+    ///
+    /// Returns current time
+    ///
+    fn now(&self) -> Instant;
+}
+
+///
+/// Monotonic clock based on `Instant`. Wall-clock jumps do not affect expiration of items.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MonotonicClock;
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+///
+/// Clock which time is moved only by hand. Time is shared between all clones of clock.
+///
+/// # Example
+///
+/// ```
+/// use cascara::{Clock, ManualClock};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.clone().advance(Duration::from_secs(10));
+/// assert_eq!(clock.now() - start, Duration::from_secs(10));
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    origin: Instant,
+    elapsed: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    ///
+    /// Create new clock stopped at actual time
+    ///
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            elapsed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    ///
+    /// Move time of clock forward
+    ///
+    /// # Arguments
+    ///
+    /// - `duration`: how much time elapsed
+    ///
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + Duration::from_nanos(self.elapsed.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, ManualClock, MonotonicClock};
+    use std::time::Duration;
+
+    #[test]
+    fn monotonic() {
+        let clock = MonotonicClock;
+        let start = clock.now();
+        assert!(clock.now() >= start);
+    }
+
+    #[test]
+    fn manual() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now() - start, Duration::from_millis(1500));
+    }
+}
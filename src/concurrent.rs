@@ -0,0 +1,903 @@
+use crate::cache::{Cache, OnEvict, UnitWeigher, VoidEvict};
+use crate::error::InsertError;
+use crate::loader::{LoadError, Loading, Role};
+use crate::metrics::Metrics;
+use crate::store::Storage;
+use crate::sweeper::Sweeper;
+use crate::tiny_lfu::{TinyLFUCache, TinyLFUConfig};
+use probabilistic_collections::SipHasherBuilder;
+use std::future::Future;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+///
+/// Default number of shards in concurrent cache
+///
+pub const DEFAULT_SHARDS: usize = 16;
+
+///
+/// Shard of concurrent cache with shared callback for evicted items
+///
+type Shard<K, V, E> = RwLock<Cache<K, V, Arc<E>>>;
+
+///
+/// Thread safe cache with TinyLFU admit policy.
+///
+/// Items are split into lock-striped shards selected by key hash.
+/// Every shard has own storage, but all shards share one TinyLFU admit filter.
+///
+/// Readers of different keys do not wait for each other on shard lock, but not every read is parallel:
+/// every lookup increments shared TinyLFU under its `Mutex`, so all readers are serialized for the short increment.
+/// Readers of the same shard are serialized also on metrics, if they are collected,
+/// and on eviction policy, if it tracks access of items.
+///
+pub struct ConcurrentCache<K, V, E = VoidEvict<K, V>>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+{
+    hasher_builder: SipHasherBuilder,
+    shards: Arc<Vec<Shard<K, V, E>>>,
+    loading: Loading<K, V>,
+    sweeper: Option<Sweeper>,
+}
+
+impl<K: Eq + Hash, V> ConcurrentCache<K, V> {
+    ///
+    /// Create new concurrent cache with `DEFAULT_SHARDS` shards.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// let cache = ConcurrentCache::<u8,u8>::new(100);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn new(capacity: usize) -> Self {
+        Self::with_shards(capacity, DEFAULT_SHARDS)
+    }
+
+    ///
+    /// Create new concurrent cache with defined number of shards.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `shards`: number of shards (capped by `capacity`)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// let cache = ConcurrentCache::<u8,u8>::with_shards(100, 4);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `shards` or `capacity` is 0.
+    ///
+    pub fn with_shards(capacity: usize, shards: usize) -> Self {
+        Self::build(capacity, shards, None)
+    }
+}
+
+impl<K, V, E> ConcurrentCache<K, V, E>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+{
+    ///
+    /// Create new concurrent cache with `DEFAULT_SHARDS` shards and callback for evicted items from cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `on_evict`: will be call for every item evicted from cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{ConcurrentCache, OnEvict, RemovalCause};
+    ///
+    /// #[derive(Default)]
+    /// struct Evict {}
+    ///
+    /// impl OnEvict<u8, u8> for Evict {
+    ///     fn evict(&self, k: u8, v: u8, cause: RemovalCause) {
+    ///         println!("Evict item.  k={}, v={}, cause={:?}", k, v, cause);
+    ///     }
+    /// }
+    ///
+    /// let cache = ConcurrentCache::<u8,u8,Evict>::with_on_evict(100, Evict::default());
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` is 0.
+    ///
+    pub fn with_on_evict(capacity: usize, on_evict: E) -> Self {
+        Self::with_on_evict_and_shards(capacity, on_evict, DEFAULT_SHARDS)
+    }
+
+    ///
+    /// Create new concurrent cache with defined number of shards and callback for evicted items from cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `on_evict`: will be call for every item evicted from cache.
+    /// - `shards`: number of shards (capped by `capacity`)
+    ///
+    /// # Panic
+    ///
+    /// If `shards` or `capacity` is 0.
+    ///
+    pub fn with_on_evict_and_shards(capacity: usize, on_evict: E, shards: usize) -> Self {
+        Self::build(capacity, shards, Some(on_evict))
+    }
+
+    fn build(capacity: usize, shards: usize, on_evict: Option<E>) -> Self {
+        assert_ne!(capacity, 0);
+        assert_ne!(shards, 0);
+        let shards = shards.min(capacity);
+        let hasher_builder = SipHasherBuilder::from_entropy();
+        let admit = Arc::new(Mutex::new(TinyLFUCache::with_config(
+            capacity,
+            &TinyLFUConfig::default(),
+        )));
+        let on_evict = on_evict.map(Arc::new);
+        let shards = (0..shards)
+            .map(|idx| {
+                //spread capacity so sum of all shards is equal to requested capacity
+                let shard_capacity = capacity / shards + usize::from(idx < capacity % shards);
+                RwLock::new(Cache::from_parts(
+                    Storage::with_capacity(shard_capacity),
+                    admit.clone(),
+                    on_evict.clone(),
+                    hasher_builder,
+                    UnitWeigher,
+                ))
+            })
+            .collect();
+        Self {
+            hasher_builder,
+            shards: Arc::new(shards),
+            loading: Loading::new(),
+            sweeper: None,
+        }
+    }
+
+    ///
+    /// Select shard for given key
+    ///
+    fn shard(&self, k: &K) -> &Shard<K, V, E> {
+        let key_hash = self.hasher_builder.hash_one(k);
+        &self.shards[(key_hash % self.shards.len() as u64) as usize]
+    }
+
+    ///
+    /// Return clone of item value if is in cache without counting lookup
+    ///
+    fn peek(&self, k: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.shard(k).read().unwrap().peek(k).cloned()
+    }
+
+    ///
+    /// Insert loaded item into cache and return its value. Value is returned even if insert is rejected.
+    ///
+    fn insert_loaded(&self, k: K, v: V) -> V
+    where
+        V: Clone,
+    {
+        //rejected item is not cached, but caller still gets loaded value
+        let _ = self.insert(k, v.clone());
+        v
+    }
+
+    ///
+    /// Activate metric collecting in every shard
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// let cache = ConcurrentCache::<u8,u8>::new(100).with_metrics();
+    /// assert!(cache.metrics().is_some());
+    /// ```
+    ///
+    pub fn with_metrics(self) -> Self {
+        for shard in self.shards.iter() {
+            shard.read().unwrap().enable_metrics();
+        }
+        self
+    }
+
+    ///
+    /// Start maintenance thread which removes expired items from all shards in given interval
+    /// and calls `on_evict` for them. Thread is stopped when cache is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// - `interval`: how long thread waits between two cleanups
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    /// use std::time::Duration;
+    ///
+    /// let cache = ConcurrentCache::<u8,u8>::new(100).with_sweeper(Duration::from_secs(1));
+    /// assert!(cache.insert_with_ttl(1, 1, Duration::from_secs(60)).is_ok());
+    /// drop(cache);
+    /// ```
+    ///
+    /// # Panic
+    ///
+    /// If thread can not be spawned.
+    ///
+    pub fn with_sweeper(mut self, interval: Duration) -> Self
+    where
+        K: Send + Sync + 'static,
+        V: Send + Sync + 'static,
+        E: Send + Sync + 'static,
+    {
+        let shards = Arc::downgrade(&self.shards);
+        //replaced sweeper is stopped before new one is started
+        self.sweeper = None;
+        self.sweeper = Some(Sweeper::spawn(interval, move || {
+            if let Some(shards) = shards.upgrade() {
+                for shard in shards.iter() {
+                    shard.write().unwrap().run_pending_tasks();
+                }
+            }
+        }));
+        self
+    }
+
+    ///
+    /// Returns number of shards
+    ///
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    ///
+    /// Returns how many items can be hold in cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// let cache = ConcurrentCache::<u8,u8>::new(100);
+    /// assert_eq!(cache.capacity(), 100);
+    /// ```
+    ///
+    pub fn capacity(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().capacity())
+            .sum()
+    }
+
+    ///
+    /// Returns actual number of items in cache
+    ///
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    ///
+    /// Returns true if cache is empty
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.shards
+            .iter()
+            .all(|shard| shard.read().unwrap().is_empty())
+    }
+
+    ///
+    /// Returns how many room left in all shards
+    ///
+    pub fn room_left(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().room_left())
+            .sum()
+    }
+
+    ///
+    /// Return true if item is in cache
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    pub fn contains(&self, k: &K) -> bool {
+        self.shard(k).read().unwrap().contains(k)
+    }
+
+    ///
+    /// Return clone of item value if is in cache.
+    /// Shard is locked only for read, but lookup is counted in shared TinyLFU under its lock.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    /// use std::sync::Arc;
+    ///
+    /// let cache = Arc::new(ConcurrentCache::new(100));
+    /// let writer = {
+    ///     let cache = cache.clone();
+    ///     std::thread::spawn(move || cache.insert(1, 2).is_ok())
+    /// };
+    /// assert!(writer.join().unwrap());
+    /// assert_eq!(cache.get(&1), Some(2));
+    /// ```
+    ///
+    pub fn get(&self, k: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.shard(k).read().unwrap().get(k).cloned()
+    }
+
+    ///
+    /// Update item value in place if is in cache. Returns result of `f`.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `f`: function applied on item value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// let cache = ConcurrentCache::new(100);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// assert_eq!(cache.update(&1, |v| { *v += 1; *v }), Some(2));
+    /// assert_eq!(cache.get(&1), Some(2));
+    /// ```
+    ///
+    pub fn update<F, R>(&self, k: &K, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut V) -> R,
+    {
+        self.shard(k).write().unwrap().get_mut(k).map(f)
+    }
+
+    ///
+    /// Insert item into cache. Item can be rejected (return Err) if shard is full and estimate of new item is lower than sample item from shard.
+    /// If item is inserted, than preview item value can be returned. If cache has `on_evict`, preview value is moved to it
+    /// with `Replaced` cause and is not returned.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    ///
+    pub fn insert(&self, k: K, v: V) -> Result<Option<V>, InsertError<K, V>> {
+        self.insert_with_ttl(k, v, Duration::from_secs(0))
+    }
+
+    ///
+    /// Insert item into cache with defined time to life.
+    ///
+    /// If expiration is zero, than item is insert without ttl. Expiration has millisecond precision.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `expiration`: how long should item lives
+    ///
+    pub fn insert_with_ttl(
+        &self,
+        k: K,
+        v: V,
+        expiration: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.shard(&k)
+            .write()
+            .unwrap()
+            .insert_with_ttl(k, v, expiration)
+    }
+
+    ///
+    /// Insert item into cache with defined time to idle.
+    /// Item expires when it is not accessed for this time.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `time_to_idle`: how long can item lives without access
+    ///
+    pub fn insert_with_tti(
+        &self,
+        k: K,
+        v: V,
+        time_to_idle: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.shard(&k)
+            .write()
+            .unwrap()
+            .insert_with_tti(k, v, time_to_idle)
+    }
+
+    ///
+    /// Insert item into cache with defined time to live and time to idle.
+    /// Item expires when it is not accessed for `time_to_idle` or after `expiration`, whichever comes first.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `expiration`: how long should item lives
+    /// - `time_to_idle`: how long can item lives without access
+    ///
+    pub fn insert_with_ttl_and_tti(
+        &self,
+        k: K,
+        v: V,
+        expiration: Duration,
+        time_to_idle: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.shard(&k)
+            .write()
+            .unwrap()
+            .insert_with_ttl_and_tti(k, v, expiration, time_to_idle)
+    }
+
+    ///
+    /// Insert item with defined cost into cache.
+    /// Cost is checked against capacity of shard selected for the item.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `cost`: item cost
+    ///
+    pub fn insert_with_cost(
+        &self,
+        k: K,
+        v: V,
+        cost: usize,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        self.shard(&k).write().unwrap().insert_with_cost(k, v, cost)
+    }
+
+    ///
+    /// Return clone of item value if is in cache, otherwise compute value with `f` and insert it into cache.
+    ///
+    /// Concurrent misses for the same key wait for one computation and all of them get its value.
+    /// Computed value is returned even if its insert into cache is rejected.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `f`: computes value of missing item
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// let cache = ConcurrentCache::new(100);
+    /// assert_eq!(cache.get_or_insert_with(1, || 2), 2);
+    /// assert_eq!(cache.get_or_insert_with(1, || 3), 2);
+    /// ```
+    ///
+    pub fn get_or_insert_with<F>(&self, k: K, f: F) -> V
+    where
+        K: Clone,
+        V: Clone,
+        F: FnOnce() -> V,
+    {
+        let key_hash = self.hasher_builder.hash_one(&k);
+        let mut f = Some(f);
+        loop {
+            if let Some(v) = self.get(&k) {
+                return v;
+            }
+            match self.loading.join(key_hash, &k) {
+                Role::Leader(guard) => {
+                    //previous leader could insert item after miss of this caller
+                    if let Some(v) = self.peek(&k) {
+                        guard.complete(Ok(v.clone()));
+                        return v;
+                    }
+                    let f = f.take().expect("loader is called only once");
+                    let v = self.insert_loaded(k, f());
+                    guard.complete(Ok(v.clone()));
+                    return v;
+                }
+                Role::Waiter(in_flight) => {
+                    //abandoned load or load with failed loader is repeated by this caller
+                    if let Some(Ok(v)) = in_flight.wait() {
+                        return v;
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Return clone of item value if is in cache, otherwise load value with async `loader` and insert it into cache.
+    ///
+    /// Concurrent misses for the same key wait for one in-flight load and all of them get its result.
+    /// Error of loader is passed to every waiter and nothing is inserted into cache.
+    /// Loaded value is returned even if its insert into cache is rejected.
+    ///
+    /// Future does not depend on any async runtime.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `loader`: loads value of missing item
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::ConcurrentCache;
+    ///
+    /// async fn load(cache: &ConcurrentCache<u64, String>) -> Result<String, std::sync::Arc<std::io::Error>> {
+    ///     cache.get_or_load(1, || async { Ok("loaded".to_string()) }).await
+    /// }
+    /// ```
+    ///
+    pub async fn get_or_load<F, Fut, Err>(&self, k: K, loader: F) -> Result<V, Arc<Err>>
+    where
+        K: Clone,
+        V: Clone,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, Err>>,
+        Err: Send + Sync + 'static,
+    {
+        let key_hash = self.hasher_builder.hash_one(&k);
+        let mut loader = Some(loader);
+        loop {
+            if let Some(v) = self.get(&k) {
+                return Ok(v);
+            }
+            let in_flight = match self.loading.join(key_hash, &k) {
+                Role::Leader(guard) => {
+                    //previous leader could insert item after miss of this caller
+                    if let Some(v) = self.peek(&k) {
+                        guard.complete(Ok(v.clone()));
+                        return Ok(v);
+                    }
+                    let loader = loader.take().expect("loader is called only once");
+                    return match loader().await {
+                        Ok(v) => {
+                            let v = self.insert_loaded(k, v);
+                            guard.complete(Ok(v.clone()));
+                            Ok(v)
+                        }
+                        Err(err) => {
+                            let err = Arc::new(err);
+                            guard.complete(Err(err.clone() as LoadError));
+                            Err(err)
+                        }
+                    };
+                }
+                Role::Waiter(in_flight) => in_flight,
+            };
+            match in_flight.wait_async().await {
+                Some(Ok(v)) => return Ok(v),
+                Some(Err(err)) => {
+                    if let Ok(err) = err.downcast::<Err>() {
+                        return Err(err);
+                    }
+                    //error of other loader type, so load item on its own
+                }
+                None => {}
+            }
+        }
+    }
+
+    ///
+    /// Remove and return item from cache.
+    /// If cache has `on_evict`, value is moved to it with `Explicit` cause and is not returned.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    pub fn remove(&self, k: &K) -> Option<V> {
+        self.shard(k).write().unwrap().remove(k)
+    }
+
+    ///
+    /// Remove item from cache and pass it to `on_evict` with `Explicit` cause.
+    /// Returns true if item was in cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    ///
+    pub fn invalidate(&self, k: &K) -> bool {
+        self.shard(k).write().unwrap().invalidate(k)
+    }
+
+    ///
+    /// Remove all expired items from all shards and call `on_evict` for them.
+    ///
+    pub fn run_pending_tasks(&self) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().run_pending_tasks();
+        }
+    }
+
+    ///
+    /// Remove all items from cache.
+    ///
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().clear();
+        }
+    }
+
+    ///
+    /// Return metrics collected over all shards
+    ///
+    pub fn metrics(&self) -> Option<Metrics> {
+        let mut result: Option<Metrics> = None;
+        for shard in self.shards.iter() {
+            if let Some(metrics) = shard.read().unwrap().metrics() {
+                result.get_or_insert_with(Metrics::new).merge(&metrics);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{OnEvict, RemovalCause};
+    use crate::concurrent::ConcurrentCache;
+    use crate::listener::AsyncEvict;
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::Thread;
+    use std::time::{Duration, Instant};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<ConcurrentCache<u64, String>>();
+        assert_send_sync::<ConcurrentCache<u64, String, AsyncEvict<u64, String>>>();
+    }
+
+    #[test]
+    fn shards_capacity() {
+        let cache = ConcurrentCache::<u8, u8>::with_shards(10, 4);
+        assert_eq!(cache.shards(), 4);
+        assert_eq!(cache.capacity(), 10);
+        let cache = ConcurrentCache::<u8, u8>::with_shards(2, 4);
+        assert_eq!(cache.shards(), 2);
+        assert_eq!(cache.capacity(), 2);
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let cache = ConcurrentCache::new(100).with_metrics();
+        assert!(cache.is_empty());
+        assert_eq!(cache.insert(1, 1), Ok(None));
+        assert_eq!(cache.insert(1, 2), Ok(Some(1)));
+        assert_eq!(cache.get(&1), Some(2));
+        assert_eq!(cache.get(&2), None);
+        assert!(cache.contains(&1));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.room_left(), 99);
+        let metrics = cache.metrics().unwrap();
+        assert_eq!(metrics.hits(), 1);
+        assert_eq!(metrics.misses(), 1);
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let cache = ConcurrentCache::new(100);
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        assert_eq!(cache.remove(&1), Some(1));
+        assert!(!cache.contains(&1));
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn parallel_inserts() {
+        let cache = Arc::new(ConcurrentCache::with_shards(1000, 8));
+        let handles = (0..4_u64)
+            .map(|t| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        assert!(cache.insert(t * 100 + i, i).is_ok());
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(cache.len(), 400);
+        assert_eq!(cache.get(&305), Some(5));
+    }
+
+    struct CountEvict {
+        evicted: Arc<AtomicUsize>,
+    }
+
+    impl OnEvict<u64, u64> for CountEvict {
+        fn evict(&self, _k: u64, _v: u64, _cause: RemovalCause) {
+            self.evicted.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn shared_on_evict() {
+        let evicted = Arc::new(AtomicUsize::new(0));
+        let on_evict = CountEvict {
+            evicted: evicted.clone(),
+        };
+        let cache = ConcurrentCache::with_on_evict_and_shards(4, on_evict, 2);
+        let admitted = (0..100).filter(|i| cache.insert(*i, *i).is_ok()).count();
+        assert!(cache.len() <= 4);
+        //every admitted item is still in cache or was evicted by any shard
+        assert_eq!(evicted.load(Ordering::SeqCst), admitted - cache.len());
+        assert!(evicted.load(Ordering::SeqCst) > 0);
+    }
+
+    #[test]
+    fn sweeper() {
+        let evicted = Arc::new(AtomicUsize::new(0));
+        let on_evict = CountEvict {
+            evicted: evicted.clone(),
+        };
+        let cache = ConcurrentCache::with_on_evict_and_shards(10, on_evict, 2)
+            .with_sweeper(Duration::from_millis(1));
+        assert!(cache
+            .insert_with_ttl(1, 1, Duration::from_millis(1))
+            .is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while evicted.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            std::thread::yield_now();
+        }
+        assert_eq!(evicted.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.len(), 1);
+        drop(cache);
+        assert_eq!(Arc::strong_count(&evicted), 1);
+    }
+
+    #[test]
+    fn get_or_insert_with_coalesce() {
+        let cache = Arc::new(ConcurrentCache::new(100));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handles = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                let calls = calls.clone();
+                std::thread::spawn(move || {
+                    cache.get_or_insert_with(1, || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(100));
+                        2
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 2);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&1), Some(2));
+    }
+
+    #[test]
+    fn get_or_insert_with_rejected() {
+        let cache = ConcurrentCache::with_shards(1, 1);
+        assert!(cache.insert(1, 1).is_ok());
+        for _ in 0..5 {
+            assert_eq!(cache.get(&1), Some(1));
+        }
+        assert_eq!(cache.get_or_insert_with(2, || 2), 2);
+        assert!(!cache.contains(&2));
+    }
+
+    #[test]
+    fn get_or_load_coalesce() {
+        let cache = Arc::new(ConcurrentCache::<u64, u64>::new(100));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handles = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                let calls = calls.clone();
+                std::thread::spawn(move || {
+                    block_on(cache.get_or_load(1, || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(100));
+                        async { Ok::<u64, String>(2) }
+                    }))
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Ok(2));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&1), Some(2));
+    }
+
+    #[test]
+    fn get_or_load_error() {
+        let cache = Arc::new(ConcurrentCache::<u64, u64>::new(100));
+        let handles = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    block_on(cache.get_or_load(1, || {
+                        std::thread::sleep(Duration::from_millis(100));
+                        async { Err::<u64, String>("failed".to_string()) }
+                    }))
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let err = handle.join().unwrap().unwrap_err();
+            assert_eq!(err.as_str(), "failed");
+        }
+        assert!(!cache.contains(&1));
+    }
+}
@@ -0,0 +1,428 @@
+//!
+//! Conformance tests for custom implementations of `Store`, `TinyLFU` and `Expiration`.
+//!
+//! Every function builds fresh instances by given constructor and panics with description of broken contract.
+//!
+//! # Example
+//!
+//! ```
+//! use cascara::conformance;
+//! use cascara::{SampledPolicy, Storage, TimerWheel, TinyLFUCache};
+//!
+//! conformance::store(|capacity, clock| Storage::with_clock(capacity, SampledPolicy, clock));
+//! conformance::tiny_lfu(|| TinyLFUCache::new(100));
+//! conformance::expiration(TimerWheel::new);
+//! ```
+//!
+
+use crate::cache::{OnEvict, RemovalCause};
+use crate::clock::ManualClock;
+use crate::store::{Item, Store};
+use crate::tiny_lfu::{TinyLFU, TinyLFUCache};
+use crate::ttl::Expiration;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+///
+/// Record of items passed to `OnEvict` during cleanup
+///
+#[derive(Default)]
+struct RecordEvict {
+    evicted: RefCell<Vec<(u64, RemovalCause)>>,
+}
+
+impl OnEvict<u64, u64> for RecordEvict {
+    fn evict(&self, k: u64, _v: u64, cause: RemovalCause) {
+        self.evicted.borrow_mut().push((k, cause));
+    }
+}
+
+///
+/// Check contract of store.
+///
+/// Store has to measure expiration of items by given clock, which is advanced by hand instead of sleep.
+///
+/// # Arguments
+///
+/// - `new`: create empty store with given capacity and clock
+///
+/// # Panic
+///
+/// If store breaks contract of `Store` trait.
+///
+pub fn store<S, F>(new: F)
+where
+    S: Store<u64, u64>,
+    F: Fn(usize, ManualClock) -> S,
+{
+    store_empty(new(10, ManualClock::new()));
+    store_insert_and_get(new(10, ManualClock::new()));
+    store_remove(new(10, ManualClock::new()));
+    store_ttl(new(10, ManualClock::new()));
+    let clock = ManualClock::new();
+    store_cleanup(new(10, clock.clone()), clock);
+    store_iterate(new(10, ManualClock::new()));
+    store_sample(new(10, ManualClock::new()));
+}
+
+fn store_empty<S: Store<u64, u64>>(store: S) {
+    assert_eq!(store.capacity(), 10, "capacity of new store");
+    assert_eq!(store.len(), 0, "len of new store");
+    assert!(store.is_empty(), "new store is empty");
+    assert_eq!(store.cost(), 0, "cost of new store");
+    assert_eq!(store.room_left(), 10, "room left in new store");
+    assert!(!store.contains(&1), "new store contains no item");
+    assert!(store.get(&1).is_none(), "new store returns no item");
+    assert_eq!(store.keys().count(), 0, "new store has no keys");
+}
+
+fn store_insert_and_get<S: Store<u64, u64>>(mut store: S) {
+    assert!(
+        store.insert(1, Item::new(1, 10)).is_none(),
+        "insert of new item returns no previous item"
+    );
+    assert!(store.contains(&1), "store contains inserted item");
+    assert_eq!(store.len(), 1, "len after insert");
+    assert_eq!(store.cost(), 1, "cost after insert");
+    assert_eq!(store.room_left(), 9, "room left after insert");
+    assert_eq!(
+        store.get(&1).map(|item| item.v),
+        Some(10),
+        "get returns inserted item"
+    );
+    assert_eq!(
+        store.peek(&1).map(|item| item.v),
+        Some(10),
+        "peek returns inserted item"
+    );
+    if let Some(item) = store.get_mut(&1) {
+        item.v = 11;
+    }
+    assert_eq!(
+        store.peek_mut(&1).map(|item| item.v),
+        Some(11),
+        "get_mut changes item"
+    );
+    let previous = store.insert(1, Item::with_cost(1, 12, 3));
+    assert_eq!(
+        previous.map(|item| item.v),
+        Some(11),
+        "insert returns replaced item"
+    );
+    assert_eq!(store.len(), 1, "len after replace");
+    assert_eq!(store.cost(), 3, "cost after replace");
+    assert_eq!(
+        store.keys().copied().collect::<Vec<u64>>(),
+        vec![1],
+        "keys after replace"
+    );
+}
+
+fn store_remove<S: Store<u64, u64>>(mut store: S) {
+    store.insert(1, Item::with_cost(1, 10, 2));
+    assert_eq!(
+        store.remove(&1).map(|item| item.v),
+        Some(10),
+        "remove returns item"
+    );
+    assert!(store.remove(&1).is_none(), "item is removed only once");
+    assert!(!store.contains(&1), "store does not contain removed item");
+    assert_eq!(store.cost(), 0, "cost after remove");
+    assert!(store.is_empty(), "store is empty after remove");
+}
+
+fn store_ttl<S: Store<u64, u64>>(mut store: S) {
+    let now = store.now();
+    store.insert_with_ttl(1, Item::new(1, 10), Duration::from_secs(60));
+    let expiration_time = store.peek(&1).and_then(|item| item.expiration_time());
+    assert!(
+        expiration_time.is_some_and(|time| time >= now + Duration::from_secs(60)),
+        "item inserted with time to live has expiration time"
+    );
+    store.insert(2, Item::new(2, 20));
+    assert!(
+        store
+            .peek(&2)
+            .and_then(|item| item.expiration_time())
+            .is_none(),
+        "item inserted without time to live never expires"
+    );
+    store.set_ttl(&2, Duration::from_secs(30));
+    assert!(
+        store
+            .peek(&2)
+            .and_then(|item| item.expiration_time())
+            .is_some(),
+        "set_ttl sets expiration time"
+    );
+    store.set_ttl(&1, Duration::from_secs(0));
+    assert!(
+        store
+            .peek(&1)
+            .and_then(|item| item.expiration_time())
+            .is_none(),
+        "set_ttl with zero duration removes expiration time"
+    );
+}
+
+fn store_cleanup<S: Store<u64, u64>>(mut store: S, clock: ManualClock) {
+    store.insert_with_ttl(1, Item::new(1, 10), Duration::from_millis(1));
+    store.insert(2, Item::new(2, 20));
+    clock.advance(Duration::from_millis(10));
+    assert!(store.peek(&1).is_none(), "expired item is not returned");
+    assert!(!store.contains(&1), "store does not contain expired item");
+    assert!(
+        store.peek_raw(&1).is_some(),
+        "expired item is kept until cleanup"
+    );
+    let on_evict = Some(RecordEvict::default());
+    store.cleanup(&on_evict);
+    assert_eq!(
+        on_evict.map(|on_evict| on_evict.evicted.into_inner()),
+        Some(vec![(1, RemovalCause::Expired)]),
+        "cleanup passes expired items to on_evict"
+    );
+    assert_eq!(store.len(), 1, "cleanup removes expired items");
+    assert_eq!(store.cost(), 1, "cost after cleanup");
+}
+
+fn store_iterate<S: Store<u64, u64>>(mut store: S) {
+    for k in 1..=3 {
+        store.insert(k, Item::new(k, k));
+    }
+    for item in store.values_mut() {
+        item.v += 10;
+    }
+    let mut items = store
+        .drain()
+        .map(|item| (item.k, item.v))
+        .collect::<Vec<(u64, u64)>>();
+    items.sort_unstable();
+    assert_eq!(
+        items,
+        vec![(1, 11), (2, 12), (3, 13)],
+        "values_mut and drain"
+    );
+    assert!(store.is_empty(), "store is empty after drain");
+    assert_eq!(store.cost(), 0, "cost after drain");
+    for k in 1..=3 {
+        store.insert(k, Item::new(k, k));
+    }
+    store.clear();
+    assert!(store.is_empty(), "store is empty after clear");
+    assert_eq!(store.room_left(), 10, "room left after clear");
+    for k in 1..=3 {
+        store.insert(k, Item::new(k, k));
+    }
+    assert_eq!(
+        store.into_values().count(),
+        3,
+        "into_values returns all items"
+    );
+}
+
+fn store_sample<S: Store<u64, u64>>(mut store: S) {
+    let admit = TinyLFUCache::new(100);
+    assert!(
+        store.sample(&admit, &[]).is_none(),
+        "empty store has no sample"
+    );
+    for k in 1..=3 {
+        store.insert(k, Item::new(k, k));
+    }
+    let sample = store.sample(&admit, &[1]);
+    assert!(
+        sample.is_some_and(|sample| sample.key == 2 || sample.key == 3),
+        "sample returns not excluded item"
+    );
+    assert!(
+        store.sample(&admit, &[1, 2, 3]).is_none(),
+        "all items are excluded"
+    );
+}
+
+///
+/// Check contract of TinyLFU.
+///
+/// Window size of TinyLFU should be small, because aging is checked after two full windows.
+///
+/// # Arguments
+///
+/// - `new`: create empty TinyLFU
+///
+/// # Panic
+///
+/// If TinyLFU breaks contract of `TinyLFU` trait.
+///
+pub fn tiny_lfu<A, F>(new: F)
+where
+    A: TinyLFU,
+    F: Fn() -> A,
+{
+    let mut admit = new();
+    let window_size = admit.window_size();
+    assert_ne!(window_size, 0, "window size is not zero");
+    assert_eq!(admit.estimate(&1), 0, "estimate of unknown item");
+    let increments = (window_size / 2).clamp(1, 15);
+    for _ in 0..increments {
+        admit.increment(&1);
+    }
+    let estimate = admit.estimate(&1);
+    assert!(
+        estimate >= increments as i64,
+        "estimate is not lower than number of increments"
+    );
+    admit.reset();
+    assert!(
+        admit.estimate(&1) <= estimate,
+        "reset does not increase estimate"
+    );
+    admit.clear();
+    assert_eq!(admit.estimate(&1), 0, "estimate after clear");
+
+    let mut admit = new();
+    for _ in 0..increments {
+        admit.increment(&1);
+    }
+    let estimate = admit.estimate(&1);
+    for k in 0..2 * window_size as u64 {
+        admit.increment(&(k + 2));
+    }
+    assert!(
+        admit.estimate(&1) < estimate,
+        "estimate is aged after full window"
+    );
+}
+
+///
+/// Check contract of expiration.
+///
+/// # Arguments
+///
+/// - `new`: create empty expiration with given origin time, which is not later than any expiration time
+///
+/// # Panic
+///
+/// If expiration breaks contract of `Expiration` trait.
+///
+pub fn expiration<X, F>(new: F)
+where
+    X: Expiration,
+    F: Fn(Instant) -> X,
+{
+    let now = Instant::now();
+    let mut expiration = new(now);
+    assert!(expiration.is_empty(), "new expiration is empty");
+    assert!(
+        expiration.insert(1, &now, Duration::from_secs(0)).is_none(),
+        "zero duration means no expiration time"
+    );
+    assert!(
+        expiration.is_empty(),
+        "item without expiration time is not kept"
+    );
+    let expiration_time = expiration.insert(1, &now, Duration::from_secs(10));
+    assert_eq!(
+        expiration_time,
+        Some(now + Duration::from_secs(10)),
+        "insert returns expiration time"
+    );
+    assert!(!expiration.is_empty(), "inserted item is kept");
+    assert!(
+        expiration
+            .cleanup(&(now + Duration::from_secs(5)))
+            .is_empty(),
+        "cleanup does not return not expired item"
+    );
+    let expiration_time = expiration.update(
+        1,
+        &(now + Duration::from_secs(10)),
+        &now,
+        Duration::from_secs(20),
+    );
+    assert_eq!(
+        expiration_time,
+        Some(now + Duration::from_secs(20)),
+        "update returns new expiration time"
+    );
+    assert!(
+        expiration
+            .cleanup(&(now + Duration::from_secs(11)))
+            .is_empty(),
+        "cleanup uses updated expiration time"
+    );
+    assert!(
+        expiration
+            .cleanup(&(now + Duration::from_secs(21)))
+            .contains(&1),
+        "cleanup returns expired item"
+    );
+    assert!(expiration.is_empty(), "cleanup removes expired item");
+    let expiration_time = expiration.insert(2, &now, Duration::from_secs(30)).unwrap();
+    assert!(
+        expiration.remove(&2, &expiration_time),
+        "remove returns true for existing item"
+    );
+    assert!(
+        !expiration.remove(&2, &expiration_time),
+        "item is removed only once"
+    );
+    expiration.insert(3, &now, Duration::from_secs(30));
+    expiration.clear();
+    assert!(expiration.is_empty(), "expiration is empty after clear");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conformance;
+    use crate::policy::{
+        ArcPolicy, FifoPolicy, LfuPolicy, LirsPolicy, LruPolicy, S3FifoPolicy, SampledPolicy,
+        WindowTinyLFUPolicy,
+    };
+    use crate::store::Storage;
+    use crate::tiny_lfu::{CompactTinyLFU, TinyLFUCache, TinyLFUConfig};
+    use crate::ttl::TimerWheel;
+
+    #[test]
+    fn storage() {
+        conformance::store(|capacity, clock| Storage::with_clock(capacity, SampledPolicy, clock));
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(
+                capacity,
+                WindowTinyLFUPolicy::with_capacity(capacity),
+                clock,
+            )
+        });
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(capacity, LruPolicy::new(), clock)
+        });
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(capacity, LfuPolicy::new(), clock)
+        });
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(capacity, FifoPolicy::new(), clock)
+        });
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(capacity, ArcPolicy::with_capacity(capacity), clock)
+        });
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(capacity, LirsPolicy::with_capacity(capacity), clock)
+        });
+        conformance::store(|capacity, clock| {
+            Storage::with_clock(capacity, S3FifoPolicy::with_capacity(capacity), clock)
+        });
+    }
+
+    #[test]
+    fn tiny_lfu() {
+        conformance::tiny_lfu(|| TinyLFUCache::new(16));
+        conformance::tiny_lfu(|| {
+            CompactTinyLFU::with_config(16, &TinyLFUConfig::default().with_window_size(32))
+        });
+    }
+
+    #[test]
+    fn expiration() {
+        conformance::expiration(TimerWheel::new);
+    }
+}
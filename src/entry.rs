@@ -0,0 +1,475 @@
+use crate::cache::{Cache, OnEvict, Weigher};
+use crate::error::InsertError;
+use crate::expiry::Expiry;
+use crate::store::{Item, Store};
+use crate::tiny_lfu::TinyLFU;
+use std::hash::{BuildHasher, Hash};
+use std::time::Duration;
+
+///
+/// A view into a single entry in a cache, which may either be vacant or occupied.
+///
+/// This enum is constructed from the `entry` method on `Cache`.
+///
+pub enum Entry<'a, K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    ///
+    /// An occupied entry
+    ///
+    Occupied(OccupiedEntry<'a, K, V, E, S, A, H, W, X>),
+
+    ///
+    /// A vacant entry
+    ///
+    Vacant(VacantEntry<'a, K, V, E, S, A, H, W, X>),
+}
+
+impl<'a, K, V, E, S, A, H, W, X> Entry<'a, K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    ///
+    /// Returns a reference to this entry's key
+    ///
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    ///
+    /// Ensures a value is in the entry by inserting the default if empty, and returns a mutable reference to the value.
+    /// Insert of vacant entry can be rejected (return Err) if cache is full and estimate of new item is too low.
+    ///
+    /// # Arguments
+    ///
+    /// - `default`: value inserted into vacant entry
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert_eq!(cache.entry(1).or_insert(1), Ok(&mut 1));
+    /// assert_eq!(cache.entry(1).or_insert(2), Ok(&mut 1));
+    /// ```
+    ///
+    pub fn or_insert(self, default: V) -> Result<&'a mut V, InsertError<K, V>> {
+        match self {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    ///
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value.
+    /// Insert of vacant entry can be rejected (return Err) if cache is full and estimate of new item is too low.
+    ///
+    /// # Arguments
+    ///
+    /// - `default`: computes value inserted into vacant entry
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert_eq!(cache.entry(1).or_insert_with(|| 1), Ok(&mut 1));
+    /// ```
+    ///
+    pub fn or_insert_with<F: FnOnce() -> V>(
+        self,
+        default: F,
+    ) -> Result<&'a mut V, InsertError<K, V>> {
+        match self {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    ///
+    /// Provides in-place mutable access to an occupied entry before any potential inserts into the cache.
+    ///
+    /// # Arguments
+    ///
+    /// - `f`: modifies value of occupied entry
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert_eq!(cache.entry(1).and_modify(|v| *v += 1).or_insert(1), Ok(&mut 1));
+    /// assert_eq!(cache.entry(1).and_modify(|v| *v += 1).or_insert(1), Ok(&mut 2));
+    /// ```
+    ///
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+///
+/// A view into an occupied entry in a cache. It is part of the `Entry` enum.
+///
+pub struct OccupiedEntry<'a, K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    cache: &'a mut Cache<K, V, E, S, A, H, W, X>,
+    key_hash: u64,
+    k: K,
+}
+
+impl<'a, K, V, E, S, A, H, W, X> OccupiedEntry<'a, K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    pub(crate) fn new(cache: &'a mut Cache<K, V, E, S, A, H, W, X>, key_hash: u64, k: K) -> Self {
+        Self { cache, key_hash, k }
+    }
+
+    fn item(&self) -> &Item<K, V> {
+        self.cache
+            .store
+            .peek_raw(&self.key_hash)
+            .expect("occupied item")
+    }
+
+    fn item_mut(&mut self) -> &mut Item<K, V> {
+        self.cache
+            .store
+            .peek_raw_mut(&self.key_hash)
+            .expect("occupied item")
+    }
+
+    ///
+    /// Returns a reference to this entry's key
+    ///
+    pub fn key(&self) -> &K {
+        &self.k
+    }
+
+    ///
+    /// Returns a reference to the value in the entry.
+    /// Item found by `Cache::entry` is returned even if its time to live elapsed meanwhile.
+    ///
+    pub fn get(&self) -> &V {
+        &self.item().v
+    }
+
+    ///
+    /// Returns a mutable reference to the value in the entry
+    ///
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.item_mut().v
+    }
+
+    ///
+    /// Converts the entry into a mutable reference to its value with a lifetime bound to the cache
+    ///
+    pub fn into_mut(self) -> &'a mut V {
+        let item = self
+            .cache
+            .store
+            .peek_raw_mut(&self.key_hash)
+            .expect("occupied item");
+        &mut item.v
+    }
+
+    ///
+    /// Replace value of the entry and return the old one.
+    /// If cache has `on_evict`, old value is moved to it with `Replaced` cause and is not returned.
+    /// Insert can be rejected (return Err) if new value has bigger cost and there is not enough room for it.
+    ///
+    /// # Arguments
+    ///
+    /// - `v`: new item value
+    ///
+    pub fn insert(self, v: V) -> Result<Option<V>, InsertError<K, V>> {
+        self.insert_with_ttl(v, Duration::from_secs(0))
+    }
+
+    ///
+    /// Replace value of the entry with defined time to life and return the old one.
+    /// If cache has `on_evict`, old value is moved to it with `Replaced` cause and is not returned.
+    /// Insert can be rejected (return Err) if new value has bigger cost and there is not enough room for it.
+    ///
+    /// If expiration is zero, than item is insert without ttl. Expiration has millisecond precision.
+    ///
+    /// # Arguments
+    ///
+    /// - `v`: new item value
+    /// - `expiration`: how long should item lives
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, Entry};
+    /// use std::time::Duration;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// if let Entry::Occupied(entry) = cache.entry(1) {
+    ///     assert_eq!(entry.insert_with_ttl(2, Duration::from_secs(10)), Ok(Some(1)));
+    /// }
+    /// assert_eq!(cache.get(&1), Some(&2));
+    /// ```
+    ///
+    pub fn insert_with_ttl(
+        self,
+        v: V,
+        expiration: Duration,
+    ) -> Result<Option<V>, InsertError<K, V>> {
+        let cost = self.cache.weight(&self.k, &v);
+        let item = Item::with_cost(self.k, v, cost);
+        self.cache.insert_hashed(self.key_hash, item, expiration)
+    }
+
+    ///
+    /// Take the value out of the entry and return it.
+    /// If cache has `on_evict`, value is moved to it with `Explicit` cause and is not returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, Entry};
+    ///
+    /// let mut cache = Cache::new(100);
+    /// assert!(cache.insert(1, 1).is_ok());
+    /// if let Entry::Occupied(entry) = cache.entry(1) {
+    ///     assert_eq!(entry.remove(), Some(1));
+    /// }
+    /// assert!(!cache.contains(&1));
+    /// ```
+    ///
+    pub fn remove(self) -> Option<V> {
+        self.cache.remove_hashed(&self.key_hash)
+    }
+}
+
+///
+/// A view into a vacant entry in a cache. It is part of the `Entry` enum.
+///
+pub struct VacantEntry<'a, K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    cache: &'a mut Cache<K, V, E, S, A, H, W, X>,
+    key_hash: u64,
+    k: K,
+}
+
+impl<'a, K, V, E, S, A, H, W, X> VacantEntry<'a, K, V, E, S, A, H, W, X>
+where
+    K: Eq + Hash,
+    E: OnEvict<K, V>,
+    S: Store<K, V>,
+    A: TinyLFU,
+    H: BuildHasher,
+    W: Weigher<K, V>,
+    X: Expiry<K, V>,
+{
+    pub(crate) fn new(cache: &'a mut Cache<K, V, E, S, A, H, W, X>, key_hash: u64, k: K) -> Self {
+        Self { cache, key_hash, k }
+    }
+
+    ///
+    /// Returns a reference to this entry's key
+    ///
+    pub fn key(&self) -> &K {
+        &self.k
+    }
+
+    ///
+    /// Take ownership of the key
+    ///
+    pub fn into_key(self) -> K {
+        self.k
+    }
+
+    ///
+    /// Insert value into the entry and return a mutable reference to it.
+    /// Item can be rejected (return Err) if cache is full and estimate of new item is lower than estimate of victims.
+    ///
+    /// # Arguments
+    ///
+    /// - `v`: item value
+    ///
+    pub fn insert(self, v: V) -> Result<&'a mut V, InsertError<K, V>> {
+        self.insert_with_ttl(v, Duration::from_secs(0))
+    }
+
+    ///
+    /// Insert value with defined time to life into the entry and return a mutable reference to it.
+    /// Item can be rejected (return Err) if cache is full and estimate of new item is lower than estimate of victims.
+    ///
+    /// If expiration is zero, than item is insert without ttl. Expiration has millisecond precision.
+    /// Reference is returned even if expiration elapsed before insert finished, following lookups do not find such item.
+    ///
+    /// # Arguments
+    ///
+    /// - `v`: item value
+    /// - `expiration`: how long should item lives
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::{Cache, Entry};
+    /// use std::time::Duration;
+    ///
+    /// let mut cache = Cache::new(100);
+    /// if let Entry::Vacant(entry) = cache.entry(1) {
+    ///     assert_eq!(entry.insert_with_ttl(1, Duration::from_secs(10)), Ok(&mut 1));
+    /// }
+    /// assert!(cache.contains(&1));
+    /// ```
+    ///
+    pub fn insert_with_ttl(
+        self,
+        v: V,
+        expiration: Duration,
+    ) -> Result<&'a mut V, InsertError<K, V>> {
+        let cost = self.cache.weight(&self.k, &v);
+        let item = Item::with_cost(self.k, v, cost);
+        self.cache.insert_hashed(self.key_hash, item, expiration)?;
+        let item = self
+            .cache
+            .store
+            .peek_raw_mut(&self.key_hash)
+            .expect("inserted item");
+        Ok(&mut item.v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cache, Entry, ManualClock};
+    use std::time::Duration;
+
+    #[test]
+    fn occupied() {
+        let mut cache = Cache::new(10);
+        assert!(cache.insert(1, 1).is_ok());
+        match cache.entry(1) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &1);
+                *entry.get_mut() += 1;
+                assert_eq!(entry.get(), &2);
+                assert_eq!(entry.insert(3), Ok(Some(2)));
+            }
+            Entry::Vacant(_) => panic!("Item should be in cache"),
+        }
+        assert_eq!(cache.get(&1), Some(&3));
+    }
+
+    #[test]
+    fn vacant() {
+        let mut cache = Cache::new(10);
+        match cache.entry(1) {
+            Entry::Occupied(_) => panic!("Item should not be in cache"),
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.key(), &1);
+                assert_eq!(entry.insert(1), Ok(&mut 1));
+            }
+        }
+        assert_eq!(cache.get(&1), Some(&1));
+    }
+
+    #[test]
+    fn remove() {
+        let mut cache = Cache::new(10);
+        assert!(cache.insert(1, 1).is_ok());
+        if let Entry::Occupied(entry) = cache.entry(1) {
+            assert_eq!(entry.remove(), Some(1));
+        } else {
+            panic!("Item should be in cache");
+        }
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn reject_vacant_insert() {
+        let mut cache = Cache::new(1);
+        assert!(cache.insert(1, 1).is_ok());
+        for _ in 0..10 {
+            assert!(cache.get(&1).is_some());
+        }
+        assert!(cache.entry(2).or_insert(2).is_err());
+        assert!(!cache.contains(&2));
+    }
+
+    #[test]
+    fn occupied_expires_after_lookup() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::with_clock(10, clock.clone());
+        assert!(cache.insert_with_ttl(1, 1, Duration::from_secs(1)).is_ok());
+        match cache.entry(1) {
+            Entry::Occupied(entry) => {
+                clock.advance(Duration::from_secs(2));
+                assert_eq!(entry.get(), &1);
+                assert_eq!(entry.into_mut(), &mut 1);
+            }
+            Entry::Vacant(_) => panic!("Item should be in cache"),
+        }
+        assert!(!cache.contains(&1));
+    }
+
+    #[test]
+    fn vacant_insert_with_elapsed_ttl() {
+        let mut cache = Cache::new(10);
+        match cache.entry(1) {
+            Entry::Occupied(_) => panic!("Item should not be in cache"),
+            Entry::Vacant(entry) => {
+                assert_eq!(
+                    entry.insert_with_ttl(1, Duration::from_nanos(1)),
+                    Ok(&mut 1)
+                );
+            }
+        }
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(!cache.contains(&1));
+    }
+}
@@ -0,0 +1,302 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+///
+/// Error returned when item is not inserted into cache.
+///
+/// Rejected item is returned back to the caller.
+///
+#[derive(Clone, PartialEq, Eq)]
+pub enum InsertError<K, V> {
+    ///
+    /// Estimate of incoming item is lower than combined estimate of victims, which have to be removed to release room.
+    ///
+    Rejected {
+        k: K,
+        v: V,
+        incoming_estimate: i64,
+        victim_estimate: i64,
+    },
+
+    ///
+    /// Cost of item is bigger than capacity of cache, so item never fits into cache.
+    ///
+    TooCostly {
+        k: K,
+        v: V,
+        cost: usize,
+        capacity: usize,
+    },
+
+    ///
+    /// Eviction of items can not release enough room for cost of item,
+    /// or item needs more victims than are sampled for one insert.
+    ///
+    NoRoom {
+        k: K,
+        v: V,
+        cost: usize,
+        room: usize,
+    },
+}
+
+impl<K, V> InsertError<K, V> {
+    ///
+    /// Returns key of not inserted item
+    ///
+    pub fn key(&self) -> &K {
+        match self {
+            InsertError::Rejected { k, .. }
+            | InsertError::TooCostly { k, .. }
+            | InsertError::NoRoom { k, .. } => k,
+        }
+    }
+
+    ///
+    /// Returns value of not inserted item
+    ///
+    pub fn value(&self) -> &V {
+        match self {
+            InsertError::Rejected { v, .. }
+            | InsertError::TooCostly { v, .. }
+            | InsertError::NoRoom { v, .. } => v,
+        }
+    }
+
+    ///
+    /// Returns ownership of not inserted item
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cascara::Cache;
+    ///
+    /// let mut cache = Cache::new(10);
+    /// let err = cache.insert_with_cost(1, "value", 11).unwrap_err();
+    /// assert_eq!(err.into_inner(), (1, "value"));
+    /// ```
+    ///
+    pub fn into_inner(self) -> (K, V) {
+        match self {
+            InsertError::Rejected { k, v, .. }
+            | InsertError::TooCostly { k, v, .. }
+            | InsertError::NoRoom { k, v, .. } => (k, v),
+        }
+    }
+}
+
+impl<K, V> Debug for InsertError<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InsertError::Rejected {
+                incoming_estimate,
+                victim_estimate,
+                ..
+            } => f
+                .debug_struct("Rejected")
+                .field("incoming_estimate", incoming_estimate)
+                .field("victim_estimate", victim_estimate)
+                .finish_non_exhaustive(),
+            InsertError::TooCostly { cost, capacity, .. } => f
+                .debug_struct("TooCostly")
+                .field("cost", cost)
+                .field("capacity", capacity)
+                .finish_non_exhaustive(),
+            InsertError::NoRoom { cost, room, .. } => f
+                .debug_struct("NoRoom")
+                .field("cost", cost)
+                .field("room", room)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl<K, V> Display for InsertError<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InsertError::Rejected {
+                incoming_estimate,
+                victim_estimate,
+                ..
+            } => write!(
+                f,
+                "item rejected: estimate {} is lower than victim estimate {}",
+                incoming_estimate, victim_estimate
+            ),
+            InsertError::TooCostly { cost, capacity, .. } => write!(
+                f,
+                "item rejected: cost {} is bigger than capacity {}",
+                cost, capacity
+            ),
+            InsertError::NoRoom { cost, room, .. } => write!(
+                f,
+                "item rejected: cost {} is bigger than room {} which can be released",
+                cost, room
+            ),
+        }
+    }
+}
+
+impl<K, V> Error for InsertError<K, V> {}
+
+///
+/// Error returned when cache can not be built from given configuration.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    ///
+    /// Neither capacity nor store is given.
+    ///
+    MissingCapacity,
+
+    ///
+    /// Capacity of cache is 0.
+    ///
+    ZeroCapacity,
+
+    ///
+    /// Window size of TinyLFU is 0.
+    ///
+    ZeroWindowSize,
+
+    ///
+    /// Given capacity is different from capacity of given store.
+    ///
+    CapacityMismatch { capacity: usize, store: usize },
+
+    ///
+    /// Window size is set together with custom admission, which has own window size.
+    ///
+    WindowSizeWithAdmission,
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingCapacity => write!(f, "capacity or store is required"),
+            BuildError::ZeroCapacity => write!(f, "capacity must be greater than 0"),
+            BuildError::ZeroWindowSize => write!(f, "window size must be greater than 0"),
+            BuildError::CapacityMismatch { capacity, store } => write!(
+                f,
+                "capacity {} is different from store capacity {}",
+                capacity, store
+            ),
+            BuildError::WindowSizeWithAdmission => {
+                write!(f, "window size can not be set for custom admission")
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+///
+/// Error returned when snapshot of cache can not be saved or loaded.
+///
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SnapshotError {
+    ///
+    /// Snapshot can not be written, read or decoded.
+    ///
+    Encoding(bincode::Error),
+
+    ///
+    /// Snapshot was created by incompatible version of format.
+    ///
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    ///
+    /// Saved admission state has different window size than TinyLFU of cache.
+    ///
+    WindowSizeMismatch { found: usize, expected: usize },
+}
+
+#[cfg(feature = "serde")]
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Encoding(err) => write!(f, "invalid snapshot: {}", err),
+            SnapshotError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported snapshot version {}, supported version is {}",
+                found, supported
+            ),
+            SnapshotError::WindowSizeMismatch { found, expected } => write!(
+                f,
+                "admission state has window size {}, but cache has window size {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Encoding(err) => Some(err),
+            SnapshotError::UnsupportedVersion { .. } | SnapshotError::WindowSizeMismatch { .. } => {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<bincode::Error> for SnapshotError {
+    fn from(err: bincode::Error) -> Self {
+        SnapshotError::Encoding(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InsertError;
+
+    #[test]
+    fn into_inner() {
+        let err = InsertError::Rejected {
+            k: 1,
+            v: 2,
+            incoming_estimate: 1,
+            victim_estimate: 3,
+        };
+        assert_eq!(err.key(), &1);
+        assert_eq!(err.value(), &2);
+        assert_eq!(err.into_inner(), (1, 2));
+    }
+
+    #[test]
+    fn display() {
+        let err = InsertError::TooCostly {
+            k: 1,
+            v: 2,
+            cost: 11,
+            capacity: 10,
+        };
+        assert_eq!(
+            err.to_string(),
+            "item rejected: cost 11 is bigger than capacity 10"
+        );
+        assert_eq!(
+            format!("{:?}", err),
+            "TooCostly { cost: 11, capacity: 10, .. }"
+        );
+    }
+
+    #[test]
+    fn display_no_room() {
+        let err = InsertError::NoRoom {
+            k: 1,
+            v: 2,
+            cost: 3,
+            room: 2,
+        };
+        assert_eq!(
+            err.to_string(),
+            "item rejected: cost 3 is bigger than room 2 which can be released"
+        );
+        assert_eq!(err.into_inner(), (1, 2));
+    }
+}
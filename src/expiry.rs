@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+///
+/// Calculate time to live of items in cache from their key and value.
+///
+/// Returned duration is measured from actual time. Zero duration means that item never expires.
+///
+/// # Example
+///
+/// ```
+/// use cascara::{Cache, Expiry, ManualClock};
+/// use std::time::Duration;
+///
+/// struct Response {
+///     max_age: u64,
+/// }
+///
+/// struct MaxAge;
+///
+/// impl Expiry<u8, Response> for MaxAge {
+///     fn expire_after_create(&self, _k: &u8, v: &Response) -> Duration {
+///         Duration::from_secs(v.max_age)
+///     }
+/// }
+///
+/// let clock = ManualClock::new();
+/// let mut cache = Cache::with_clock(100, clock.clone()).with_expiry(MaxAge);
+/// assert!(cache.insert(1, Response { max_age: 60 }).is_ok());
+/// assert!(cache.insert(2, Response { max_age: 0 }).is_ok());
+/// clock.advance(Duration::from_secs(61));
+/// assert!(!cache.contains(&1));
+/// assert!(cache.contains(&2));
+/// ```
+///
+pub trait Expiry<K, V> {
+    ///
+    /// Returns time to live of item inserted into cache without explicit time to live.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    ///
+    fn expire_after_create(&self, k: &K, v: &V) -> Duration;
+
+    ///
+    /// Returns time to live of item after it is read from cache.
+    /// By default remaining time to live is kept. It is not called for item read at its expiration time.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `remaining`: remaining time to live of item, zero if item never expires
+    ///
+    fn expire_after_read(&self, _k: &K, _v: &V, remaining: Duration) -> Duration {
+        remaining
+    }
+
+    ///
+    /// Returns time to live of item after its value is replaced without explicit time to live.
+    /// By default remaining time to live is kept. Item replaced at its expiration time is created again instead.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: new item value
+    /// - `remaining`: remaining time to live of old item, zero if item never expires
+    ///
+    fn expire_after_update(&self, _k: &K, _v: &V, remaining: Duration) -> Duration {
+        remaining
+    }
+}
+
+///
+/// Items never expire, unless time to live is defined at insert.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VoidExpiry;
+
+impl<K, V> Expiry<K, V> for VoidExpiry {
+    fn expire_after_create(&self, _k: &K, _v: &V) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    fn expire_after_update(&self, _k: &K, _v: &V, _remaining: Duration) -> Duration {
+        Duration::from_secs(0)
+    }
+}
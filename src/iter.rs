@@ -1,13 +1,16 @@
-use crate::store::{Item, Store};
-use indexmap::map::Keys;
+use crate::store::Store;
 use std::iter::FusedIterator;
+use std::time::Instant;
 
-pub struct Iter<'a, K, V, S>
+///
+/// An iterator over the entries of a cache. Expired items are skipped.
+///
+pub struct Iter<'a, K: 'a, V: 'a, S>
 where
     S: Store<K, V>,
 {
     store: &'a S,
-    keys: Keys<'a, u64, Item<K, V>>,
+    keys: S::Keys<'a>,
 }
 
 impl<'a, K, V, S> Iter<'a, K, V, S>
@@ -27,23 +30,200 @@ where
     type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(k) = self.keys.next() {
-            if let Some(item) = self.store.get(k) {
-                Some((&item.k, &item.v))
-            } else {
-                unreachable!()
-            }
-        } else {
-            None
-        }
+        //expired items are in storage until they are cleaned up
+        let store = self.store;
+        self.keys
+            .by_ref()
+            .find_map(|k| store.peek(k))
+            .map(|item| (&item.k, &item.v))
     }
 }
 
 impl<'a, K, V, S> FusedIterator for Iter<'a, K, V, S> where S: Store<K, V> {}
 
+///
+/// A mutable iterator over the entries of a cache. Expired items are skipped.
+///
+pub struct IterMut<'a, K: 'a, V: 'a, S>
+where
+    S: Store<K, V> + 'a,
+{
+    values: S::ValuesMut<'a>,
+    now: Instant,
+}
+
+impl<'a, K, V, S> IterMut<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    pub fn new(values: S::ValuesMut<'a>, now: Instant) -> Self {
+        Self { values, now }
+    }
+}
+
+impl<'a, K, V, S> Iterator for IterMut<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.now;
+        self.values
+            .by_ref()
+            .find(|item| !item.is_expired(&now))
+            .map(|item| (&item.k, &mut item.v))
+    }
+}
+
+impl<'a, K, V, S> FusedIterator for IterMut<'a, K, V, S> where S: Store<K, V> {}
+
+///
+/// An iterator over the keys of a cache. Expired items are skipped.
+///
+pub struct Keys<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    iter: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Keys<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    pub fn new(store: &'a S) -> Self {
+        Self {
+            iter: Iter::new(store),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Keys<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, _)| k)
+    }
+}
+
+impl<'a, K, V, S> FusedIterator for Keys<'a, K, V, S> where S: Store<K, V> {}
+
+///
+/// An iterator over the values of a cache. Expired items are skipped.
+///
+pub struct Values<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    iter: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Values<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    pub fn new(store: &'a S) -> Self {
+        Self {
+            iter: Iter::new(store),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Values<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V, S> FusedIterator for Values<'a, K, V, S> where S: Store<K, V> {}
+
+///
+/// A draining iterator over the entries of a cache. Expired items are removed, but skipped.
+///
+pub struct Drain<'a, K, V, S>
+where
+    S: Store<K, V> + 'a,
+{
+    items: S::Drain<'a>,
+    now: Instant,
+}
+
+impl<'a, K, V, S> Drain<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    pub fn new(items: S::Drain<'a>, now: Instant) -> Self {
+        Self { items, now }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Drain<'a, K, V, S>
+where
+    S: Store<K, V>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.now;
+        self.items
+            .by_ref()
+            .find(|item| !item.is_expired(&now))
+            .map(|item| (item.k, item.v))
+    }
+}
+
+impl<'a, K, V, S> FusedIterator for Drain<'a, K, V, S> where S: Store<K, V> {}
+
+///
+/// An owning iterator over the entries of a cache. Expired items are skipped.
+///
+pub struct IntoIter<K, V, S>
+where
+    S: Store<K, V>,
+{
+    items: S::IntoValues,
+    now: Instant,
+}
+
+impl<K, V, S> IntoIter<K, V, S>
+where
+    S: Store<K, V>,
+{
+    pub fn new(items: S::IntoValues, now: Instant) -> Self {
+        Self { items, now }
+    }
+}
+
+impl<K, V, S> Iterator for IntoIter<K, V, S>
+where
+    S: Store<K, V>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.now;
+        self.items
+            .by_ref()
+            .find(|item| !item.is_expired(&now))
+            .map(|item| (item.k, item.v))
+    }
+}
+
+impl<K, V, S> FusedIterator for IntoIter<K, V, S> where S: Store<K, V> {}
+
 #[cfg(test)]
 mod tests {
-    use crate::Cache;
+    use crate::{Cache, ManualClock};
+    use std::time::Duration;
 
     #[test]
     fn iter() {
@@ -54,4 +234,38 @@ mod tests {
         let items = cache.iter().collect::<Vec<(&u8, &u8)>>();
         assert_eq!(items, vec![(&1, &1), (&2, &2), (&3, &3)])
     }
+
+    #[test]
+    fn iter_skip_expired() {
+        let clock = ManualClock::new();
+        let mut cache = Cache::<u8, u8, _, _>::with_clock(10, clock.clone());
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert_with_ttl(2, 2, Duration::from_secs(1)).is_ok());
+        assert!(cache.insert(3, 3).is_ok());
+        clock.advance(Duration::from_secs(2));
+        let items = cache.iter().collect::<Vec<(&u8, &u8)>>();
+        assert_eq!(items, vec![(&1, &1), (&3, &3)]);
+        assert_eq!(cache.keys().collect::<Vec<&u8>>(), vec![&1, &3]);
+        assert_eq!(cache.values().collect::<Vec<&u8>>(), vec![&1, &3]);
+        for (_, v) in cache.iter_mut() {
+            *v += 1;
+        }
+        let items = (&cache).into_iter().collect::<Vec<(&u8, &u8)>>();
+        assert_eq!(items, vec![(&1, &2), (&3, &4)]);
+        let items = cache.into_iter().collect::<Vec<(u8, u8)>>();
+        assert_eq!(items, vec![(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn drain() {
+        let mut cache = Cache::<u8, u8>::new(10);
+        assert!(cache.insert(1, 1).is_ok());
+        assert!(cache.insert(2, 2).is_ok());
+        let items = cache.drain().collect::<Vec<(u8, u8)>>();
+        assert_eq!(items, vec![(1, 1), (2, 2)]);
+        assert!(cache.is_empty());
+        assert_eq!(cache.room_left(), 10);
+        assert!(cache.insert(3, 3).is_ok());
+        assert_eq!(cache.iter().count(), 1);
+    }
 }
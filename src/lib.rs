@@ -1,9 +1,42 @@
+mod admission;
+mod builder;
 mod cache;
+mod climber;
+mod clock;
+mod concurrent;
+pub mod conformance;
+mod entry;
+mod error;
+mod expiry;
 mod iter;
+mod listener;
+mod loader;
 mod metrics;
+mod policy;
+mod sketch;
+#[cfg(feature = "serde")]
+mod snapshot;
 mod store;
+mod sweeper;
 mod tiny_lfu;
 mod ttl;
 
-pub use cache::{Cache, OnEvict};
+pub use admission::{AdmissionDecision, AdmissionRecord};
+pub use builder::CacheBuilder;
+pub use cache::{Cache, OnEvict, RemovalCause, UnitWeigher, Weigher};
+pub use clock::{Clock, ManualClock, MonotonicClock};
+pub use concurrent::ConcurrentCache;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+#[cfg(feature = "serde")]
+pub use error::SnapshotError;
+pub use error::{BuildError, InsertError};
+pub use expiry::{Expiry, VoidExpiry};
+pub use listener::{AsyncEvict, BatchEvict, Notification};
 pub use metrics::Metrics;
+pub use policy::{
+    ArcPolicy, FifoPolicy, LfuPolicy, LirsPolicy, LruPolicy, S3FifoPolicy, SampledPolicy,
+    WindowTinyLFUPolicy,
+};
+pub use store::{Item, SampleItem, Storage, Store};
+pub use tiny_lfu::{CompactTinyLFU, TinyLFU, TinyLFUCache, TinyLFUConfig, VoidTinyLFU};
+pub use ttl::{Expiration, TimerWheel};
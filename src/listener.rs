@@ -0,0 +1,223 @@
+use crate::cache::{OnEvict, RemovalCause};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+///
+/// Removed item with reason of its removal
+///
+pub type Notification<K, V> = (K, V, RemovalCause);
+
+///
+/// Listener which process removed items in batches on dedicated thread
+///
+pub trait BatchEvict<K, V> {
+    ///
+    /// Process batch of removed items
+    ///
+    /// # Arguments
+    ///
+    /// - `batch`: removed items in order of their removal
+    /// - `dropped`: how many notifications were dropped since previous batch, because channel was full
+    ///
+    fn evict_batch(&mut self, batch: Vec<Notification<K, V>>, dropped: u64);
+}
+
+impl<K, V, F> BatchEvict<K, V> for F
+where
+    F: FnMut(Vec<Notification<K, V>>, u64),
+{
+    fn evict_batch(&mut self, batch: Vec<Notification<K, V>>, dropped: u64) {
+        self(batch, dropped)
+    }
+}
+
+///
+/// Callback for evicted items which does not block cache operations.
+///
+/// Removed items are sent into bounded channel and processed by listener on dedicated thread in batches.
+/// If channel is full, than notification is dropped and counted.
+/// Remaining notifications are processed and thread is stopped when callback is dropped.
+///
+/// # Example
+///
+/// ```
+/// use cascara::{AsyncEvict, Cache, Notification};
+///
+/// let on_evict = AsyncEvict::new(1024, 64, |batch: Vec<Notification<u8, u8>>, dropped: u64| {
+///     println!("Evicted {} items, dropped {} notifications", batch.len(), dropped);
+/// });
+/// let mut cache = Cache::with_on_evict(100, on_evict);
+/// assert!(cache.insert(1, 1).is_ok());
+/// assert!(cache.invalidate(&1));
+/// ```
+///
+pub struct AsyncEvict<K, V> {
+    sender: Option<SyncSender<Notification<K, V>>>,
+    dropped: Arc<AtomicU64>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<K, V> AsyncEvict<K, V>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+{
+    ///
+    /// Create new callback and start thread for listener
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max number of notifications waiting in channel
+    /// - `batch_size`: max number of notifications passed to listener at once
+    /// - `listener`: processor of removed items
+    ///
+    /// # Panic
+    ///
+    /// If `capacity` or `batch_size` is 0.
+    /// If thread can not be spawned.
+    ///
+    pub fn new<L>(capacity: usize, batch_size: usize, listener: L) -> Self
+    where
+        L: BatchEvict<K, V> + Send + 'static,
+    {
+        assert_ne!(capacity, 0);
+        assert_ne!(batch_size, 0);
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let handle = {
+            let dropped = dropped.clone();
+            thread::Builder::new()
+                .name("cascara-evict".to_string())
+                .spawn(move || Self::listen(receiver, batch_size, dropped, listener))
+                .expect("spawn eviction listener thread")
+        };
+        Self {
+            sender: Some(sender),
+            dropped,
+            handle: Some(handle),
+        }
+    }
+
+    ///
+    /// Pass received notifications in batches to listener until all senders are dropped
+    ///
+    fn listen<L>(
+        receiver: Receiver<Notification<K, V>>,
+        batch_size: usize,
+        dropped: Arc<AtomicU64>,
+        mut listener: L,
+    ) where
+        L: BatchEvict<K, V>,
+    {
+        let mut reported = 0;
+        while let Ok(notification) = receiver.recv() {
+            let mut batch = Vec::with_capacity(batch_size);
+            batch.push(notification);
+            batch.extend(receiver.try_iter().take(batch_size - 1));
+            let total = dropped.load(Ordering::Acquire);
+            listener.evict_batch(batch, total - reported);
+            reported = total;
+        }
+        let total = dropped.load(Ordering::Acquire);
+        if total > reported {
+            listener.evict_batch(Vec::new(), total - reported);
+        }
+    }
+}
+
+impl<K, V> AsyncEvict<K, V> {
+    ///
+    /// Returns total number of dropped notifications
+    ///
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Acquire)
+    }
+}
+
+impl<K, V> OnEvict<K, V> for AsyncEvict<K, V> {
+    fn evict(&self, k: K, v: V, cause: RemovalCause) {
+        if let Some(sender) = &self.sender {
+            //full channel means slow listener, so notification is dropped instead of blocking cache
+            if sender.try_send((k, v, cause)).is_err() {
+                self.dropped.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+    }
+}
+
+impl<K, V> Drop for AsyncEvict<K, V> {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(handle) = self.handle.take() {
+            //panic in listener is already reported by thread itself
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{OnEvict, RemovalCause};
+    use crate::listener::{AsyncEvict, Notification};
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+
+    type Batches = Arc<Mutex<Vec<(Vec<Notification<u64, u64>>, u64)>>>;
+
+    #[test]
+    fn batches() {
+        let batches = Batches::default();
+        let on_evict = {
+            let batches = batches.clone();
+            AsyncEvict::new(10, 2, move |batch, dropped| {
+                batches.lock().unwrap().push((batch, dropped));
+            })
+        };
+        for i in 0..3 {
+            on_evict.evict(i, i, RemovalCause::Size);
+        }
+        drop(on_evict);
+        let batches = batches.lock().unwrap();
+        let items = batches
+            .iter()
+            .flat_map(|(batch, _)| batch.iter().map(|(k, _, _)| *k))
+            .collect::<Vec<u64>>();
+        assert_eq!(items, vec![0, 1, 2]);
+        assert!(batches.iter().all(|(batch, _)| batch.len() <= 2));
+        assert!(batches.iter().all(|(_, dropped)| *dropped == 0));
+    }
+
+    #[test]
+    fn dropped_when_full() {
+        let batches = Batches::default();
+        let (started, wait_started) = mpsc::channel();
+        let (release, wait_release) = mpsc::channel::<()>();
+        let on_evict = {
+            let batches = batches.clone();
+            AsyncEvict::new(1, 10, move |batch, dropped| {
+                if batches.lock().unwrap().is_empty() {
+                    started.send(()).unwrap();
+                    wait_release.recv().unwrap();
+                }
+                batches.lock().unwrap().push((batch, dropped));
+            })
+        };
+        on_evict.evict(1, 1, RemovalCause::Size);
+        wait_started.recv().unwrap();
+        on_evict.evict(2, 2, RemovalCause::Expired);
+        on_evict.evict(3, 3, RemovalCause::Expired);
+        assert_eq!(on_evict.dropped(), 1);
+        release.send(()).unwrap();
+        drop(on_evict);
+        let batches = batches.lock().unwrap();
+        assert_eq!(
+            *batches,
+            vec![
+                (vec![(1, 1, RemovalCause::Size)], 0),
+                (vec![(2, 2, RemovalCause::Expired)], 1)
+            ]
+        );
+    }
+}
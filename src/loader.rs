@@ -0,0 +1,302 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+///
+/// Type erased error of loader shared by all waiters
+///
+pub type LoadError = Arc<dyn Any + Send + Sync>;
+
+///
+/// Result of finished load shared by all waiters
+///
+pub type LoadResult<V> = Result<V, LoadError>;
+
+///
+/// State of in-flight load
+///
+enum LoadState<V> {
+    Loading(Vec<Waker>),
+    Done(LoadResult<V>),
+    Abandoned,
+}
+
+///
+/// Load of one item which is in progress. All concurrent misses for the same item wait on it.
+///
+pub struct InFlight<K, V> {
+    k: K,
+    state: Mutex<LoadState<V>>,
+    ready: Condvar,
+}
+
+impl<K, V> InFlight<K, V>
+where
+    V: Clone,
+{
+    fn new(k: K) -> Self {
+        Self {
+            k,
+            state: Mutex::new(LoadState::Loading(Vec::new())),
+            ready: Condvar::new(),
+        }
+    }
+
+    ///
+    /// Set final state of load and wake up all waiters
+    ///
+    fn finish(&self, state: LoadState<V>) {
+        let previous = std::mem::replace(&mut *self.state.lock().unwrap(), state);
+        if let LoadState::Loading(wakers) = previous {
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+        self.ready.notify_all();
+    }
+
+    ///
+    /// Block current thread until load is finished.
+    /// Returns None if load was abandoned.
+    ///
+    pub fn wait(&self) -> Option<LoadResult<V>> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            match &*state {
+                LoadState::Loading(_) => state = self.ready.wait(state).unwrap(),
+                LoadState::Done(result) => return Some(result.clone()),
+                LoadState::Abandoned => return None,
+            }
+        }
+    }
+
+    ///
+    /// Returns future which is resolved when load is finished.
+    /// Future returns None if load was abandoned.
+    ///
+    pub fn wait_async(self: Arc<Self>) -> WaitLoad<K, V> {
+        WaitLoad { in_flight: self }
+    }
+}
+
+///
+/// Future resolved when in-flight load is finished
+///
+pub struct WaitLoad<K, V> {
+    in_flight: Arc<InFlight<K, V>>,
+}
+
+impl<K, V> Future for WaitLoad<K, V>
+where
+    V: Clone,
+{
+    type Output = Option<LoadResult<V>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.in_flight.state.lock().unwrap();
+        match &mut *state {
+            LoadState::Loading(wakers) => {
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+            LoadState::Done(result) => Poll::Ready(Some(result.clone())),
+            LoadState::Abandoned => Poll::Ready(None),
+        }
+    }
+}
+
+///
+/// Role of caller in coalesced load
+///
+pub enum Role<'a, K, V>
+where
+    V: Clone,
+{
+    ///
+    /// Caller has to load item and finish load with guard
+    ///
+    Leader(LoadGuard<'a, K, V>),
+
+    ///
+    /// Item is loaded by other caller
+    ///
+    Waiter(Arc<InFlight<K, V>>),
+}
+
+///
+/// Guard of in-flight load owned by leader.
+///
+/// If guard is dropped without finished load (panic or cancelled future), than load is abandoned
+/// and waiters try to load item on their own.
+///
+pub struct LoadGuard<'a, K, V>
+where
+    V: Clone,
+{
+    key_hash: u64,
+    in_flight: Arc<InFlight<K, V>>,
+    loading: &'a Loading<K, V>,
+    finished: bool,
+}
+
+impl<'a, K, V> LoadGuard<'a, K, V>
+where
+    V: Clone,
+{
+    ///
+    /// Finish load and pass result to all waiters
+    ///
+    pub fn complete(mut self, result: LoadResult<V>) {
+        self.finished = true;
+        self.loading.remove(self.key_hash, &self.in_flight);
+        self.in_flight.finish(LoadState::Done(result));
+    }
+}
+
+impl<'a, K, V> Drop for LoadGuard<'a, K, V>
+where
+    V: Clone,
+{
+    fn drop(&mut self) {
+        if !self.finished {
+            self.loading.remove(self.key_hash, &self.in_flight);
+            self.in_flight.finish(LoadState::Abandoned);
+        }
+    }
+}
+
+///
+/// Registry of in-flight loads indexed by key hash
+///
+pub struct Loading<K, V> {
+    in_flight: Mutex<HashMap<u64, Arc<InFlight<K, V>>>>,
+}
+
+impl<K, V> Loading<K, V> {
+    ///
+    /// Create new empty registry
+    ///
+    pub fn new() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    ///
+    /// Remove in-flight load from registry if it is still registered
+    ///
+    fn remove(&self, key_hash: u64, current: &Arc<InFlight<K, V>>) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key_hash)
+            .is_some_and(|registered| Arc::ptr_eq(registered, current))
+        {
+            in_flight.remove(&key_hash);
+        }
+    }
+}
+
+impl<K, V> Default for Loading<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Loading<K, V>
+where
+    K: Eq + Clone,
+    V: Clone,
+{
+    ///
+    /// Join load of given item.
+    ///
+    /// First caller becomes leader, other callers for the same key wait for it.
+    /// Colliding key with the same hash is loaded without coalescing.
+    ///
+    pub fn join(&self, key_hash: u64, k: &K) -> Role<'_, K, V> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(current) = in_flight.get(&key_hash) {
+            if current.k.eq(k) {
+                return Role::Waiter(current.clone());
+            }
+            let current = Arc::new(InFlight::new(k.clone()));
+            return Role::Leader(self.guard(key_hash, current));
+        }
+        let current = Arc::new(InFlight::new(k.clone()));
+        in_flight.insert(key_hash, current.clone());
+        Role::Leader(self.guard(key_hash, current))
+    }
+
+    fn guard(&self, key_hash: u64, in_flight: Arc<InFlight<K, V>>) -> LoadGuard<'_, K, V> {
+        LoadGuard {
+            key_hash,
+            in_flight,
+            loading: self,
+            finished: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::{Loading, Role};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+
+    struct NoopWake;
+
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn leader_and_waiter() {
+        let loading = Loading::<u64, u64>::new();
+        let leader = loading.join(1, &1);
+        let waiter = loading.join(1, &1);
+        match (leader, waiter) {
+            (Role::Leader(guard), Role::Waiter(in_flight)) => {
+                let waker = Arc::new(NoopWake).into();
+                let mut cx = Context::from_waker(&waker);
+                let mut wait = in_flight.clone().wait_async();
+                assert!(Pin::new(&mut wait).poll(&mut cx).is_pending());
+                guard.complete(Ok(2));
+                assert!(matches!(
+                    Pin::new(&mut wait).poll(&mut cx),
+                    Poll::Ready(Some(Ok(2)))
+                ));
+                assert!(matches!(in_flight.wait(), Some(Ok(2))));
+            }
+            _ => panic!("First caller should be leader"),
+        }
+        assert!(matches!(loading.join(1, &1), Role::Leader(_)));
+    }
+
+    #[test]
+    fn colliding_key_is_leader() {
+        let loading = Loading::<u64, u64>::new();
+        let _leader = loading.join(1, &1);
+        assert!(matches!(loading.join(1, &2), Role::Leader(_)));
+    }
+
+    #[test]
+    fn abandoned() {
+        let loading = Loading::<u64, u64>::new();
+        let leader = loading.join(1, &1);
+        let waiter = loading.join(1, &1);
+        drop(leader);
+        if let Role::Waiter(in_flight) = waiter {
+            assert!(in_flight.wait().is_none());
+        } else {
+            panic!("Second caller should be waiter");
+        }
+        assert!(matches!(loading.join(1, &1), Role::Leader(_)));
+    }
+}
@@ -20,6 +20,7 @@ pub enum MetricType {
 #[derive(Clone)]
 pub struct Metrics {
     all: [[usize; 256]; METRICS],
+    policy_state: Vec<(&'static str, usize)>,
 }
 
 impl Metrics {
@@ -29,6 +30,7 @@ impl Metrics {
     pub fn new() -> Self {
         Self {
             all: [[0; 256]; METRICS],
+            policy_state: Vec::new(),
         }
     }
 
@@ -96,11 +98,58 @@ impl Metrics {
         hits as f64 / (hits + misses) as f64
     }
 
+    ///
+    /// Named value of eviction policy state, e.g. `arc_p` for target size of ARC recency segment
+    ///
+    pub fn policy_state(&self, name: &str) -> Option<usize> {
+        self.policy_state
+            .iter()
+            .find(|(state, _)| state.eq(&name))
+            .map(|(_, value)| *value)
+    }
+
+    ///
+    /// All named values of eviction policy state
+    ///
+    pub fn policy_states(&self) -> &[(&'static str, usize)] {
+        &self.policy_state
+    }
+
+    ///
+    /// Replace state of eviction policy
+    ///
+    pub(crate) fn set_policy_state(&mut self, policy_state: Vec<(&'static str, usize)>) {
+        self.policy_state = policy_state;
+    }
+
+    ///
+    /// Add all collected data from other metrics.
+    /// Values of policy state with the same name are summed.
+    ///
+    pub fn merge(&mut self, other: &Metrics) {
+        for (vals, other_vals) in self.all.iter_mut().zip(other.all.iter()) {
+            for (val, other_val) in vals.iter_mut().zip(other_vals.iter()) {
+                *val += other_val;
+            }
+        }
+        for (name, other_value) in &other.policy_state {
+            match self
+                .policy_state
+                .iter_mut()
+                .find(|(state, _)| state == name)
+            {
+                Some((_, value)) => *value += other_value,
+                None => self.policy_state.push((name, *other_value)),
+            }
+        }
+    }
+
     ///
     /// Clear all collected metrics data for every category
     ///
     pub fn clear(&mut self) {
         self.all = [[0; 256]; METRICS];
+        self.policy_state.clear();
     }
 }
 
@@ -112,13 +161,17 @@ impl Default for Metrics {
 
 impl Debug for Metrics {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Metrics")
+        let mut debug = f.debug_struct("Metrics");
+        debug
             .field("hits", &self.hits())
             .field("misses", &self.misses())
             .field("keys_inserted", &self.keys_inserted())
             .field("keys_updated", &self.keys_updated())
-            .field("keys_evicted", &self.keys_evicted())
-            .finish()
+            .field("keys_evicted", &self.keys_evicted());
+        if !self.policy_state.is_empty() {
+            debug.field("policy_state", &self.policy_state);
+        }
+        debug.finish()
     }
 }
 
@@ -222,6 +275,33 @@ mod tests {
         assert_eq!(metrics.misses(), 0);
     }
 
+    #[test]
+    fn merge() {
+        let mut metrics = Metrics::new();
+        metrics.insert(MetricType::Hit, &1, 1);
+        let mut other = Metrics::new();
+        other.insert(MetricType::Hit, &2, 2);
+        other.insert(MetricType::Miss, &1, 1);
+        metrics.merge(&other);
+        assert_eq!(metrics.hits(), 3);
+        assert_eq!(metrics.misses(), 1);
+    }
+
+    #[test]
+    fn policy_state() {
+        let mut metrics = Metrics::new();
+        metrics.set_policy_state(vec![("arc_p", 2), ("arc_t1", 3)]);
+        let mut other = Metrics::new();
+        other.set_policy_state(vec![("arc_p", 1)]);
+        metrics.merge(&other);
+        assert_eq!(metrics.policy_state("arc_p"), Some(3));
+        assert_eq!(metrics.policy_state("arc_t1"), Some(3));
+        assert_eq!(metrics.policy_state("arc_t2"), None);
+        assert_eq!(metrics.policy_states().len(), 2);
+        metrics.clear();
+        assert!(metrics.policy_states().is_empty());
+    }
+
     #[test]
     fn debug() {
         let mut metrics = Metrics::new();
@@ -0,0 +1,208 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+
+///
+/// Adaptive replacement cache (ARC) policy.
+///
+/// Resident items are split into recency list T1 (seen once) and frequency list T2 (seen at least twice).
+/// Evicted items are remembered in ghost lists B1 and B2. Insert of item from ghost list adapts target size `p` of T1:
+/// hit in B1 grows T1, hit in B2 shrinks it. Victim is the oldest item from T1 if T1 exceeds `p`, otherwise from T2.
+///
+/// Because victims are selected before incoming item is inserted, target size is adapted after eviction.
+/// Incoming items are always admitted.
+///
+#[derive(Clone, Debug)]
+pub struct ArcPolicy {
+    t1: OrderList,
+    t2: OrderList,
+    b1: OrderList,
+    b2: OrderList,
+    p: usize,
+    capacity: usize,
+}
+
+impl ArcPolicy {
+    ///
+    /// Create new policy for storage with defined capacity
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            t1: OrderList::new(),
+            t2: OrderList::new(),
+            b1: OrderList::new(),
+            b2: OrderList::new(),
+            p: 0,
+            capacity,
+        }
+    }
+
+    ///
+    /// Returns target size of recency list T1
+    ///
+    pub fn target(&self) -> usize {
+        self.p
+    }
+
+    ///
+    /// Drop the oldest ghosts, so T1 with B1 fits capacity and all lists fit double capacity
+    ///
+    fn trim_ghosts(&mut self) {
+        while self.t1.cost() + self.b1.cost() > self.capacity && self.b1.pop_back().is_some() {}
+        while self.t1.cost() + self.t2.cost() + self.b1.cost() + self.b2.cost() > 2 * self.capacity
+            && self.b2.pop_back().is_some()
+        {}
+    }
+}
+
+impl Policy for ArcPolicy {
+    fn insert(&mut self, k: u64, cost: usize) {
+        if self.b1.remove(&k).is_some() {
+            let delta = (self.b2.len() / (self.b1.len() + 1)).max(1) * cost;
+            self.p = (self.p + delta).min(self.capacity);
+            self.t2.push_front(k, cost);
+        } else if self.b2.remove(&k).is_some() {
+            let delta = (self.b1.len() / (self.b2.len() + 1)).max(1) * cost;
+            self.p = self.p.saturating_sub(delta);
+            self.t2.push_front(k, cost);
+        } else {
+            self.t1.push_front(k, cost);
+        }
+        self.trim_ghosts();
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        if self.t1.set_cost(&k, cost) || self.t2.set_cost(&k, cost) {
+            self.access(&k);
+        } else {
+            self.insert(k, cost);
+        }
+    }
+
+    fn access(&mut self, k: &u64) {
+        if let Some(cost) = self.t1.remove(k) {
+            self.t2.push_front(*k, cost);
+        } else {
+            self.t2.move_to_front(k);
+        }
+    }
+
+    fn remove(&mut self, k: &u64) {
+        if self.t1.remove(k).is_none() {
+            self.t2.remove(k);
+        }
+    }
+
+    fn evict(&mut self, k: &u64) {
+        if let Some(cost) = self.t1.remove(k) {
+            self.b1.push_front(*k, cost);
+        } else if let Some(cost) = self.t2.remove(k) {
+            self.b2.push_front(*k, cost);
+        }
+        self.trim_ghosts();
+    }
+
+    fn clear(&mut self) {
+        self.t1.clear();
+        self.t2.clear();
+        self.b1.clear();
+        self.b2.clear();
+        self.p = 0;
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        let victim = if self.t1.cost() > self.p {
+            self.t1
+                .back_except(exclude)
+                .or_else(|| self.t2.back_except(exclude))
+        } else {
+            self.t2
+                .back_except(exclude)
+                .or_else(|| self.t1.back_except(exclude))
+        };
+        victim.map(|(k, cost)| SampleItem::with_cost(k, admit.estimate(&k), cost))
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+
+    fn state(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("arc_p", self.p),
+            ("arc_t1", self.t1.cost()),
+            ("arc_t2", self.t2.cost()),
+            ("arc_b1", self.b1.cost()),
+            ("arc_b2", self.b2.cost()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{ArcPolicy, Policy};
+    use crate::store::Item;
+    use crate::tiny_lfu::VoidTinyLFU;
+    use indexmap::IndexMap;
+
+    fn victim(policy: &ArcPolicy) -> u64 {
+        let data = IndexMap::<u64, Item<u64, u64>>::new();
+        policy.sample(&data, &VoidTinyLFU, &[]).unwrap().key
+    }
+
+    #[test]
+    fn recency_and_frequency() {
+        let mut policy = ArcPolicy::with_capacity(3);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.insert(3, 1);
+        policy.access(&1);
+        //T1 exceeds target, so the oldest item seen once is victim
+        assert_eq!(victim(&policy), 2);
+        policy.evict(&2);
+        assert_eq!(policy.b1.len(), 1);
+        policy.insert(4, 1);
+        assert_eq!(policy.target(), 0);
+    }
+
+    #[test]
+    fn adapt_target() {
+        let mut policy = ArcPolicy::with_capacity(2);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.evict(&1);
+        //ghost hit in B1 grows recency list
+        policy.insert(1, 1);
+        assert_eq!(policy.target(), 1);
+        assert_eq!(policy.t2.len(), 1);
+        policy.evict(&1);
+        assert_eq!(policy.b2.len(), 1);
+        //ghost hit in B2 shrinks recency list
+        policy.insert(1, 1);
+        assert_eq!(policy.target(), 0);
+        assert_eq!(
+            policy.state(),
+            vec![
+                ("arc_p", 0),
+                ("arc_t1", 1),
+                ("arc_t2", 1),
+                ("arc_b1", 0),
+                ("arc_b2", 0)
+            ]
+        );
+        policy.remove(&1);
+        assert_eq!(policy.b2.len(), 0);
+        assert_eq!(policy.t2.len(), 0);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+
+///
+/// FIFO policy.
+///
+/// Items are kept in insertion order and access does not change it, so victim is the oldest inserted item.
+/// Replaced item keeps its position. Incoming items are always admitted.
+///
+#[derive(Clone, Debug, Default)]
+pub struct FifoPolicy {
+    order: OrderList,
+}
+
+impl FifoPolicy {
+    ///
+    /// Create new empty policy
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Policy for FifoPolicy {
+    const TRACKS_ACCESS: bool = false;
+
+    fn insert(&mut self, k: u64, cost: usize) {
+        self.order.push_front(k, cost);
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        if !self.order.set_cost(&k, cost) {
+            self.insert(k, cost);
+        }
+    }
+
+    fn access(&mut self, _k: &u64) {}
+
+    fn remove(&mut self, k: &u64) {
+        self.order.remove(k);
+    }
+
+    fn clear(&mut self) {
+        self.order.clear();
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        self.order
+            .back_except(exclude)
+            .map(|(k, cost)| SampleItem::with_cost(k, admit.estimate(&k), cost))
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{FifoPolicy, Policy};
+    use crate::store::Item;
+    use crate::tiny_lfu::VoidTinyLFU;
+    use indexmap::IndexMap;
+
+    #[test]
+    fn first_in_first_out() {
+        let mut policy = FifoPolicy::new();
+        let data = IndexMap::<u64, Item<u64, u64>>::new();
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.access(&1);
+        policy.update(1, 2);
+        let victim = policy.sample(&data, &VoidTinyLFU, &[]).unwrap();
+        assert_eq!((victim.key, victim.cost), (1, 2));
+        policy.remove(&1);
+        assert_eq!(policy.sample(&data, &VoidTinyLFU, &[]).unwrap().key, 2);
+        assert!(policy.sample(&data, &VoidTinyLFU, &[2]).is_none());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+///
+/// Items with the same frequency, linked with buckets of neighbouring frequencies
+///
+#[derive(Clone, Debug, Default)]
+struct Bucket {
+    items: OrderList,
+    prev: Option<u64>,
+    next: Option<u64>,
+}
+
+///
+/// Exact LFU policy with O(1) insert, access and remove.
+///
+/// Items are grouped into buckets by number of accesses since insert and buckets are linked from the lowest frequency.
+/// Victim is the least recently used item from bucket with the lowest frequency.
+/// Estimate of sampled item is its exact frequency. Incoming items are always admitted.
+///
+#[derive(Clone, Debug, Default)]
+pub struct LfuPolicy {
+    frequencies: HashMap<u64, u64>,
+    buckets: HashMap<u64, Bucket>,
+    head: Option<u64>,
+}
+
+impl LfuPolicy {
+    ///
+    /// Create new empty policy
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Returns exact frequency of item, if it is tracked by policy
+    ///
+    pub fn frequency(&self, k: &u64) -> Option<u64> {
+        self.frequencies.get(k).copied()
+    }
+
+    ///
+    /// Push item into bucket with given frequency. Missing bucket is linked after bucket `prev`, or as head.
+    ///
+    fn push(&mut self, frequency: u64, prev: Option<u64>, k: u64, cost: usize) {
+        if !self.buckets.contains_key(&frequency) {
+            let next = match prev {
+                Some(prev) => self.buckets[&prev].next,
+                None => self.head,
+            };
+            match prev {
+                Some(prev) => {
+                    self.buckets.get_mut(&prev).expect("prev bucket").next = Some(frequency)
+                }
+                None => self.head = Some(frequency),
+            }
+            if let Some(next) = next {
+                self.buckets.get_mut(&next).expect("next bucket").prev = Some(frequency);
+            }
+            self.buckets.insert(
+                frequency,
+                Bucket {
+                    items: OrderList::new(),
+                    prev,
+                    next,
+                },
+            );
+        }
+        self.buckets
+            .get_mut(&frequency)
+            .expect("frequency bucket")
+            .items
+            .push_front(k, cost);
+        self.frequencies.insert(k, frequency);
+    }
+
+    ///
+    /// Remove item from bucket with given frequency and unlink bucket if it is empty.
+    /// Returns item cost.
+    ///
+    fn pull(&mut self, frequency: u64, k: &u64) -> usize {
+        let bucket = self.buckets.get_mut(&frequency).expect("frequency bucket");
+        let cost = bucket.items.remove(k).expect("bucket item");
+        if bucket.items.len() == 0 {
+            let bucket = self.buckets.remove(&frequency).expect("frequency bucket");
+            match bucket.prev {
+                Some(prev) => self.buckets.get_mut(&prev).expect("prev bucket").next = bucket.next,
+                None => self.head = bucket.next,
+            }
+            if let Some(next) = bucket.next {
+                self.buckets.get_mut(&next).expect("next bucket").prev = bucket.prev;
+            }
+        }
+        cost
+    }
+}
+
+impl Policy for LfuPolicy {
+    fn insert(&mut self, k: u64, cost: usize) {
+        self.remove(&k);
+        self.push(1, None, k, cost);
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        if let Some(frequency) = self.frequency(&k) {
+            self.buckets
+                .get_mut(&frequency)
+                .expect("frequency bucket")
+                .items
+                .set_cost(&k, cost);
+            self.access(&k);
+        } else {
+            self.insert(k, cost);
+        }
+    }
+
+    fn access(&mut self, k: &u64) {
+        if let Some(frequency) = self.frequency(k) {
+            //bucket of next frequency is linked after actual bucket, or after its predecessor if actual bucket is emptied
+            let prev = self.buckets[&frequency].prev;
+            let cost = self.pull(frequency, k);
+            let prev = if self.buckets.contains_key(&frequency) {
+                Some(frequency)
+            } else {
+                prev
+            };
+            self.push(frequency + 1, prev, *k, cost);
+        }
+    }
+
+    fn remove(&mut self, k: &u64) {
+        if let Some(frequency) = self.frequencies.remove(k) {
+            self.pull(frequency, k);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.frequencies.clear();
+        self.buckets.clear();
+        self.head = None;
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        _admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        let mut current = self.head;
+        while let Some(frequency) = current {
+            let bucket = &self.buckets[&frequency];
+            if let Some((k, cost)) = bucket.items.back_except(exclude) {
+                return Some(SampleItem::with_cost(k, frequency as i64, cost));
+            }
+            current = bucket.next;
+        }
+        None
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{LfuPolicy, Policy};
+    use crate::store::Item;
+    use crate::tiny_lfu::VoidTinyLFU;
+    use indexmap::IndexMap;
+
+    fn buckets(policy: &LfuPolicy) -> Vec<u64> {
+        let mut result = Vec::new();
+        let mut current = policy.head;
+        while let Some(frequency) = current {
+            result.push(frequency);
+            current = policy.buckets[&frequency].next;
+        }
+        result
+    }
+
+    #[test]
+    fn least_frequently_used() {
+        let mut policy = LfuPolicy::new();
+        let data = IndexMap::<u64, Item<u64, u64>>::new();
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.insert(3, 1);
+        policy.access(&1);
+        policy.access(&1);
+        policy.access(&2);
+        let victim = policy.sample(&data, &VoidTinyLFU, &[]).unwrap();
+        assert_eq!((victim.key, victim.estimate), (3, 1));
+        let victim = policy.sample(&data, &VoidTinyLFU, &[3]).unwrap();
+        assert_eq!((victim.key, victim.estimate), (2, 2));
+        assert_eq!(policy.frequency(&1), Some(3));
+        assert!(policy.admit(0, &[]));
+    }
+
+    #[test]
+    fn linked_buckets() {
+        let mut policy = LfuPolicy::new();
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.access(&1);
+        policy.access(&1);
+        assert_eq!(buckets(&policy), vec![1, 3]);
+        policy.access(&2);
+        assert_eq!(buckets(&policy), vec![2, 3]);
+        policy.update(2, 5);
+        assert_eq!(buckets(&policy), vec![3]);
+        policy.insert(4, 1);
+        assert_eq!(buckets(&policy), vec![1, 3]);
+        policy.remove(&1);
+        policy.remove(&2);
+        assert_eq!(buckets(&policy), vec![1]);
+        policy.clear();
+        assert!(buckets(&policy).is_empty());
+        assert_eq!(policy.frequency(&4), None);
+    }
+}
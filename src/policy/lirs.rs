@@ -0,0 +1,343 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+///
+/// Default share of resident HIR items in percents of capacity
+///
+pub const DEFAULT_HIR_PERCENT: usize = 1;
+
+///
+/// Status of item tracked by LIRS
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Lir,
+    Hir,
+    Ghost,
+}
+
+///
+/// Low inter-reference recency set (LIRS) policy.
+///
+/// Items with low inter-reference recency (LIR) hold most of capacity, the rest is queue of resident HIR items.
+/// Recency stack keeps LIR items, resident HIR items and history of evicted HIR items (ghosts).
+/// HIR item accessed while it is in stack is promoted to LIR and the bottom LIR item is demoted to HIR queue.
+/// Victim is the oldest item from HIR queue, so scans do not flush LIR items.
+///
+/// Incoming items are always admitted.
+///
+#[derive(Clone, Debug)]
+pub struct LirsPolicy {
+    stack: OrderList,
+    queue: OrderList,
+    ghosts: OrderList,
+    status: HashMap<u64, Status>,
+    lir_cost: usize,
+    lir_capacity: usize,
+    ghost_capacity: usize,
+}
+
+impl LirsPolicy {
+    ///
+    /// Create new policy for storage with defined capacity.
+    /// Resident HIR items have 1% of capacity.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_hir_percent(capacity, DEFAULT_HIR_PERCENT)
+    }
+
+    ///
+    /// Create new policy for storage with defined capacity and share of resident HIR items.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    /// - `hir_percent`: share of resident HIR items in percents of capacity (at least 1 item)
+    ///
+    /// # Panic
+    ///
+    /// If `hir_percent` > 100
+    ///
+    pub fn with_hir_percent(capacity: usize, hir_percent: usize) -> Self {
+        assert!(hir_percent <= 100);
+        let hir_capacity = (capacity * hir_percent / 100).max(1);
+        Self {
+            stack: OrderList::new(),
+            queue: OrderList::new(),
+            ghosts: OrderList::new(),
+            status: HashMap::new(),
+            lir_cost: 0,
+            lir_capacity: capacity.saturating_sub(hir_capacity),
+            ghost_capacity: capacity,
+        }
+    }
+
+    ///
+    /// Returns capacity of LIR items
+    ///
+    pub fn lir_capacity(&self) -> usize {
+        self.lir_capacity
+    }
+
+    ///
+    /// Remove HIR items and ghosts from bottom of stack, so the bottom item is LIR
+    ///
+    fn prune(&mut self) {
+        while let Some((k, _)) = self.stack.back() {
+            match self.status.get(&k) {
+                Some(Status::Lir) => break,
+                Some(Status::Hir) => {
+                    self.stack.remove(&k);
+                }
+                _ => {
+                    self.stack.remove(&k);
+                    self.ghosts.remove(&k);
+                    self.status.remove(&k);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Demote the bottom LIR items into HIR queue until LIR items fit their capacity
+    ///
+    fn shrink_lir(&mut self) {
+        while self.lir_cost > self.lir_capacity {
+            self.prune();
+            if let Some((k, cost)) = self.stack.pop_back() {
+                self.lir_cost -= cost;
+                self.status.insert(k, Status::Hir);
+                self.queue.push_front(k, cost);
+            } else {
+                break;
+            }
+        }
+        self.prune();
+    }
+
+    ///
+    /// Forget the oldest ghosts until history fits its capacity
+    ///
+    fn trim_ghosts(&mut self) {
+        while self.ghosts.cost() > self.ghost_capacity {
+            if let Some((k, _)) = self.ghosts.pop_back() {
+                self.stack.remove(&k);
+                self.status.remove(&k);
+            }
+        }
+    }
+}
+
+impl Policy for LirsPolicy {
+    fn insert(&mut self, k: u64, cost: usize) {
+        if self.status.get(&k) == Some(&Status::Ghost) {
+            //item evicted with recent history has low inter-reference recency
+            self.ghosts.remove(&k);
+            self.stack.push_front(k, cost);
+            self.status.insert(k, Status::Lir);
+            self.lir_cost += cost;
+            self.shrink_lir();
+            return;
+        }
+        self.remove(&k);
+        self.stack.push_front(k, cost);
+        if self.lir_cost + cost <= self.lir_capacity {
+            self.status.insert(k, Status::Lir);
+            self.lir_cost += cost;
+        } else {
+            self.status.insert(k, Status::Hir);
+            self.queue.push_front(k, cost);
+        }
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        match self.status.get(&k) {
+            Some(Status::Lir) => {
+                let old_cost = self.stack.get(&k).expect("LIR item");
+                self.lir_cost = self.lir_cost - old_cost + cost;
+                self.stack.set_cost(&k, cost);
+                self.access(&k);
+                self.shrink_lir();
+            }
+            Some(Status::Hir) => {
+                self.queue.set_cost(&k, cost);
+                self.stack.set_cost(&k, cost);
+                self.access(&k);
+            }
+            _ => self.insert(k, cost),
+        }
+    }
+
+    fn access(&mut self, k: &u64) {
+        match self.status.get(k) {
+            Some(Status::Lir) => {
+                self.stack.move_to_front(k);
+                self.prune();
+            }
+            Some(Status::Hir) => {
+                let cost = self.queue.get(k).expect("HIR item");
+                if self.stack.move_to_front(k) {
+                    self.queue.remove(k);
+                    self.status.insert(*k, Status::Lir);
+                    self.lir_cost += cost;
+                    self.shrink_lir();
+                } else {
+                    self.stack.push_front(*k, cost);
+                    self.queue.move_to_front(k);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn remove(&mut self, k: &u64) {
+        match self.status.remove(k) {
+            Some(Status::Lir) => {
+                if let Some(cost) = self.stack.remove(k) {
+                    self.lir_cost -= cost;
+                }
+                self.prune();
+            }
+            Some(Status::Hir) => {
+                self.queue.remove(k);
+                self.stack.remove(k);
+            }
+            Some(Status::Ghost) => {
+                self.stack.remove(k);
+                self.ghosts.remove(k);
+            }
+            None => {}
+        }
+    }
+
+    fn evict(&mut self, k: &u64) {
+        if self.status.get(k) != Some(&Status::Hir) {
+            self.remove(k);
+            return;
+        }
+        let cost = self.queue.remove(k).expect("HIR item");
+        if self.stack.get(k).is_some() {
+            self.status.insert(*k, Status::Ghost);
+            self.ghosts.push_front(*k, cost);
+            self.trim_ghosts();
+        } else {
+            self.status.remove(k);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.stack.clear();
+        self.queue.clear();
+        self.ghosts.clear();
+        self.status.clear();
+        self.lir_cost = 0;
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        self.queue
+            .back_except(exclude)
+            .or_else(|| {
+                self.stack
+                    .iter_back()
+                    .find(|(k, _)| !exclude.contains(k) && self.status.get(k) == Some(&Status::Lir))
+            })
+            .map(|(k, cost)| SampleItem::with_cost(k, admit.estimate(&k), cost))
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+
+    fn state(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("lirs_lir", self.lir_cost),
+            ("lirs_hir", self.queue.cost()),
+            ("lirs_ghost", self.ghosts.cost()),
+            ("lirs_stack", self.stack.len()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{LirsPolicy, Policy};
+    use crate::store::Item;
+    use crate::tiny_lfu::VoidTinyLFU;
+    use indexmap::IndexMap;
+
+    fn victim(policy: &LirsPolicy) -> u64 {
+        let data = IndexMap::<u64, Item<u64, u64>>::new();
+        policy.sample(&data, &VoidTinyLFU, &[]).unwrap().key
+    }
+
+    #[test]
+    fn lir_capacity() {
+        assert_eq!(LirsPolicy::with_capacity(10).lir_capacity(), 9);
+        assert_eq!(LirsPolicy::with_capacity(1000).lir_capacity(), 990);
+        assert_eq!(LirsPolicy::with_hir_percent(100, 20).lir_capacity(), 80);
+    }
+
+    #[test]
+    fn promote_and_demote() {
+        let mut policy = LirsPolicy::with_capacity(3);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.insert(3, 1);
+        assert_eq!(victim(&policy), 3);
+        //HIR item in stack is promoted and the bottom LIR item is demoted
+        policy.access(&3);
+        assert_eq!(victim(&policy), 1);
+        policy.evict(&1);
+        assert_eq!(policy.status.get(&1), None);
+        policy.insert(4, 1);
+        assert_eq!(victim(&policy), 4);
+        policy.evict(&4);
+        assert_eq!(policy.ghosts.len(), 1);
+        policy.insert(5, 1);
+        //ghost comes back as LIR item
+        policy.insert(4, 1);
+        assert_eq!(
+            policy.state(),
+            vec![
+                ("lirs_lir", 2),
+                ("lirs_hir", 2),
+                ("lirs_ghost", 0),
+                ("lirs_stack", 3)
+            ]
+        );
+        assert_eq!(victim(&policy), 5);
+    }
+
+    #[test]
+    fn scan_resistance() {
+        let mut policy = LirsPolicy::with_capacity(3);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        for k in 10..20 {
+            policy.insert(k, 1);
+            assert_eq!(victim(&policy), k);
+            policy.evict(&k);
+        }
+        policy.access(&1);
+        policy.access(&2);
+        assert_eq!(policy.state()[0], ("lirs_lir", 2));
+        policy.remove(&1);
+        policy.remove(&2);
+        assert_eq!(policy.state()[0], ("lirs_lir", 0));
+        policy.clear();
+        assert_eq!(policy.stack.len(), 0);
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+///
+/// Node of order list with links to neighbours
+///
+#[derive(Clone, Debug)]
+struct Node {
+    prev: Option<u64>,
+    next: Option<u64>,
+    cost: usize,
+}
+
+///
+/// Doubly linked list of item identifications with O(1) insert, move and remove.
+///
+/// Front of list holds most recently pushed (or moved) items, back holds the oldest one.
+///
+#[derive(Clone, Debug, Default)]
+pub struct OrderList {
+    nodes: HashMap<u64, Node>,
+    head: Option<u64>,
+    tail: Option<u64>,
+    cost: usize,
+}
+
+impl OrderList {
+    ///
+    /// Create new empty list
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Returns number of items in list
+    ///
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    ///
+    /// Returns total cost of items in list
+    ///
+    pub fn cost(&self) -> usize {
+        self.cost
+    }
+
+    ///
+    /// Returns cost of item, if it is in list
+    ///
+    pub fn get(&self, k: &u64) -> Option<usize> {
+        self.nodes.get(k).map(|node| node.cost)
+    }
+
+    ///
+    /// Push item to front of list. Item already in list is moved.
+    ///
+    pub fn push_front(&mut self, k: u64, cost: usize) {
+        self.remove(&k);
+        let node = Node {
+            prev: None,
+            next: self.head,
+            cost,
+        };
+        if let Some(head) = self.head {
+            self.nodes.get_mut(&head).expect("list head").prev = Some(k);
+        } else {
+            self.tail = Some(k);
+        }
+        self.head = Some(k);
+        self.cost += cost;
+        self.nodes.insert(k, node);
+    }
+
+    ///
+    /// Move item to front of list. Returns false if item is not in list.
+    ///
+    pub fn move_to_front(&mut self, k: &u64) -> bool {
+        if let Some(cost) = self.remove(k) {
+            self.push_front(*k, cost);
+            true
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// Change cost of item in list. Returns false if item is not in list.
+    ///
+    pub fn set_cost(&mut self, k: &u64, cost: usize) -> bool {
+        if let Some(node) = self.nodes.get_mut(k) {
+            self.cost = self.cost - node.cost + cost;
+            node.cost = cost;
+            true
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// Remove item from list and return its cost
+    ///
+    pub fn remove(&mut self, k: &u64) -> Option<usize> {
+        let node = self.nodes.remove(k)?;
+        if let Some(prev) = node.prev {
+            self.nodes.get_mut(&prev).expect("prev node").next = node.next;
+        } else {
+            self.head = node.next;
+        }
+        if let Some(next) = node.next {
+            self.nodes.get_mut(&next).expect("next node").prev = node.prev;
+        } else {
+            self.tail = node.prev;
+        }
+        self.cost -= node.cost;
+        Some(node.cost)
+    }
+
+    ///
+    /// Returns the oldest item with its cost
+    ///
+    pub fn back(&self) -> Option<(u64, usize)> {
+        let k = self.tail?;
+        Some((k, self.nodes[&k].cost))
+    }
+
+    ///
+    /// Remove the oldest item from list and return it with its cost
+    ///
+    pub fn pop_back(&mut self) -> Option<(u64, usize)> {
+        let k = self.tail?;
+        self.remove(&k).map(|cost| (k, cost))
+    }
+
+    ///
+    /// Iterate items with their cost from the oldest one
+    ///
+    pub fn iter_back(&self) -> impl Iterator<Item = (u64, usize)> + '_ {
+        let mut current = self.tail;
+        std::iter::from_fn(move || {
+            let k = current?;
+            let node = &self.nodes[&k];
+            current = node.prev;
+            Some((k, node.cost))
+        })
+    }
+
+    ///
+    /// Returns the oldest item which is not excluded
+    ///
+    pub fn back_except(&self, exclude: &[u64]) -> Option<(u64, usize)> {
+        self.iter_back().find(|(k, _)| !exclude.contains(k))
+    }
+
+    ///
+    /// Remove all items from list
+    ///
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.head = None;
+        self.tail = None;
+        self.cost = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::list::OrderList;
+
+    #[test]
+    fn push_and_pop() {
+        let mut list = OrderList::new();
+        list.push_front(1, 1);
+        list.push_front(2, 2);
+        list.push_front(3, 3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.cost(), 6);
+        assert_eq!(list.back(), Some((1, 1)));
+        assert_eq!(list.pop_back(), Some((1, 1)));
+        assert_eq!(list.pop_back(), Some((2, 2)));
+        assert_eq!(list.pop_back(), Some((3, 3)));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn move_to_front() {
+        let mut list = OrderList::new();
+        list.push_front(1, 1);
+        list.push_front(2, 1);
+        list.push_front(3, 1);
+        assert!(list.move_to_front(&1));
+        assert!(!list.move_to_front(&4));
+        let order = list.iter_back().map(|(k, _)| k).collect::<Vec<u64>>();
+        assert_eq!(order, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn remove_middle() {
+        let mut list = OrderList::new();
+        list.push_front(1, 1);
+        list.push_front(2, 1);
+        list.push_front(3, 1);
+        assert_eq!(list.remove(&2), Some(1));
+        assert_eq!(list.remove(&2), None);
+        let order = list.iter_back().map(|(k, _)| k).collect::<Vec<u64>>();
+        assert_eq!(order, vec![1, 3]);
+        assert_eq!(list.back_except(&[1]), Some((3, 1)));
+    }
+
+    #[test]
+    fn set_cost() {
+        let mut list = OrderList::new();
+        list.push_front(1, 1);
+        assert!(list.set_cost(&1, 5));
+        assert_eq!(list.cost(), 5);
+        assert_eq!(list.get(&1), Some(5));
+        assert_eq!(list.get(&2), None);
+        list.clear();
+        assert_eq!(list.cost(), 0);
+        assert_eq!(list.pop_back(), None);
+    }
+}
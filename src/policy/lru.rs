@@ -0,0 +1,85 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+
+///
+/// Strict LRU policy.
+///
+/// Accessed item is moved to front of order list and victim is the least recently used item.
+/// Incoming items are always admitted.
+///
+#[derive(Clone, Debug, Default)]
+pub struct LruPolicy {
+    order: OrderList,
+}
+
+impl LruPolicy {
+    ///
+    /// Create new empty policy
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Policy for LruPolicy {
+    fn insert(&mut self, k: u64, cost: usize) {
+        self.order.push_front(k, cost);
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        self.order.push_front(k, cost);
+    }
+
+    fn access(&mut self, k: &u64) {
+        self.order.move_to_front(k);
+    }
+
+    fn remove(&mut self, k: &u64) {
+        self.order.remove(k);
+    }
+
+    fn clear(&mut self) {
+        self.order.clear();
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        self.order
+            .back_except(exclude)
+            .map(|(k, cost)| SampleItem::with_cost(k, admit.estimate(&k), cost))
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{LruPolicy, Policy};
+    use crate::store::Item;
+    use crate::tiny_lfu::VoidTinyLFU;
+    use indexmap::IndexMap;
+
+    #[test]
+    fn least_recently_used() {
+        let mut policy = LruPolicy::new();
+        let data = IndexMap::<u64, Item<u64, u64>>::new();
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.insert(3, 1);
+        policy.access(&1);
+        assert_eq!(policy.sample(&data, &VoidTinyLFU, &[]).unwrap().key, 2);
+        policy.update(2, 1);
+        assert_eq!(policy.sample(&data, &VoidTinyLFU, &[]).unwrap().key, 3);
+        assert_eq!(policy.sample(&data, &VoidTinyLFU, &[3]).unwrap().key, 1);
+        assert!(policy.admit(0, &[]));
+    }
+}
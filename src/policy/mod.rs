@@ -0,0 +1,133 @@
+mod arc;
+mod fifo;
+mod lfu;
+mod lirs;
+mod list;
+mod lru;
+mod s3_fifo;
+mod sampled;
+mod window;
+
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+
+pub use arc::ArcPolicy;
+pub use fifo::FifoPolicy;
+pub use lfu::LfuPolicy;
+pub use lirs::LirsPolicy;
+pub use lru::LruPolicy;
+pub use s3_fifo::S3FifoPolicy;
+pub use sampled::SampledPolicy;
+pub use window::WindowTinyLFUPolicy;
+
+///
+/// Eviction policy of storage.
+///
+/// Policy tracks order of items in storage and selects victims, which should be removed to make room for new items.
+/// Items are identified by key hash and every item has its cost.
+/// Trait is not exported, policies are chosen through exported types, so internal data of storage is not part of public API.
+///
+pub trait Policy {
+    ///
+    /// Policy depends on access of items. Storage does not lock policy on read if it is false.
+    ///
+    const TRACKS_ACCESS: bool = true;
+
+    ///
+    /// New item was inserted into storage
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    /// - `cost`: item cost
+    ///
+    fn insert(&mut self, k: u64, cost: usize);
+
+    ///
+    /// Item already in storage was replaced by new one
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    /// - `cost`: new item cost
+    ///
+    fn update(&mut self, k: u64, cost: usize) {
+        self.remove(&k);
+        self.insert(k, cost);
+    }
+
+    ///
+    /// Item in storage was accessed
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn access(&mut self, k: &u64);
+
+    ///
+    /// Item was removed from storage
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn remove(&mut self, k: &u64);
+
+    ///
+    /// Victim was evicted from storage to make room for new items.
+    /// By default eviction is the same as removal, policies with history of evicted items override it.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn evict(&mut self, k: &u64) {
+        self.remove(k);
+    }
+
+    ///
+    /// All items were removed from storage
+    ///
+    fn clear(&mut self);
+
+    ///
+    /// Return victim candidate which is not excluded, if there is any.
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: items in storage
+    /// - `admit`: TinyLFU for calculating estimate of item in storage.
+    /// - `exclude`: items which can not be returned as sample.
+    ///
+    fn sample<K, V>(
+        &self,
+        data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem>;
+
+    ///
+    /// Decide if incoming item should be admitted at the expense of victims.
+    ///
+    /// By default incoming item estimate can not be lower than combined estimate of victims.
+    ///
+    /// # Arguments
+    ///
+    /// - `incoming_estimate`: estimate of incoming item
+    /// - `victims`: victims which are removed if incoming item is admitted
+    ///
+    fn admit(&self, incoming_estimate: i64, victims: &[SampleItem]) -> bool {
+        let victims_estimate: i64 = victims.iter().map(|victim| victim.estimate).sum();
+        incoming_estimate >= victims_estimate
+    }
+
+    ///
+    /// Returns internal state of policy as named values, which are reported through `Metrics`.
+    /// By default policy has no state to report.
+    ///
+    fn state(&self) -> Vec<(&'static str, usize)> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,308 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+///
+/// Default share of small queue in percents of capacity
+///
+pub const DEFAULT_SMALL_PERCENT: usize = 10;
+
+///
+/// Max frequency counted for item
+///
+const MAX_FREQUENCY: u8 = 3;
+
+///
+/// S3-FIFO policy.
+///
+/// New items are inserted into small FIFO queue (10% of capacity). Item leaving small queue is moved into main FIFO queue
+/// if it was accessed, otherwise it is evicted and remembered in ghost queue. Item from ghost queue is inserted directly into main queue.
+/// Item leaving main queue is reinserted with decreased frequency while it was accessed, otherwise it is evicted.
+/// One-hit wonders leave quickly through small queue, so scans do not flush main queue.
+///
+/// Victim is selected without changing queues, items are moved between queues when victim is evicted.
+/// Incoming items are always admitted.
+///
+#[derive(Clone, Debug)]
+pub struct S3FifoPolicy {
+    small: OrderList,
+    main: OrderList,
+    ghost: OrderList,
+    frequencies: HashMap<u64, u8>,
+    small_capacity: usize,
+    ghost_capacity: usize,
+}
+
+impl S3FifoPolicy {
+    ///
+    /// Create new policy for storage with defined capacity.
+    /// Small queue has 10% of capacity.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_small_percent(capacity, DEFAULT_SMALL_PERCENT)
+    }
+
+    ///
+    /// Create new policy for storage with defined capacity and share of small queue.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    /// - `small_percent`: share of small queue in percents of capacity (at least 1 item)
+    ///
+    /// # Panic
+    ///
+    /// If `small_percent` > 100
+    ///
+    pub fn with_small_percent(capacity: usize, small_percent: usize) -> Self {
+        assert!(small_percent <= 100);
+        let small_capacity = (capacity * small_percent / 100).max(1);
+        Self {
+            small: OrderList::new(),
+            main: OrderList::new(),
+            ghost: OrderList::new(),
+            frequencies: HashMap::new(),
+            small_capacity,
+            ghost_capacity: capacity.saturating_sub(small_capacity).max(1),
+        }
+    }
+
+    ///
+    /// Returns capacity of small queue
+    ///
+    pub fn small_capacity(&self) -> usize {
+        self.small_capacity
+    }
+
+    fn frequency(&self, k: &u64) -> u8 {
+        self.frequencies.get(k).copied().unwrap_or_default()
+    }
+
+    ///
+    /// Returns true if victim is taken from small queue
+    ///
+    fn evict_small(&self) -> bool {
+        self.small.cost() >= self.small_capacity || self.main.len() == 0
+    }
+
+    ///
+    /// Returns the oldest not accessed item from small queue
+    ///
+    fn small_victim(&self, exclude: &[u64]) -> Option<(u64, usize)> {
+        self.small
+            .iter_back()
+            .find(|(k, _)| !exclude.contains(k) && self.frequency(k) == 0)
+    }
+
+    ///
+    /// Returns item from main queue, which leaves it first.
+    /// Every pass over main queue decreases frequency of reinserted items, so it is the oldest item with the lowest frequency.
+    ///
+    fn main_victim(&self, exclude: &[u64]) -> Option<(u64, usize)> {
+        let mut victim: Option<(u64, usize, u8)> = None;
+        for (k, cost) in self.main.iter_back().filter(|(k, _)| !exclude.contains(k)) {
+            let frequency = self.frequency(&k);
+            if frequency == 0 {
+                return Some((k, cost));
+            }
+            if victim.is_none_or(|(_, _, lowest)| frequency < lowest) {
+                victim = Some((k, cost, frequency));
+            }
+        }
+        victim.map(|(k, cost, _)| (k, cost))
+    }
+
+    ///
+    /// Move accessed items from back of small queue into main queue until `k` is at back
+    ///
+    fn rotate_small(&mut self, k: &u64) {
+        while let Some((back, cost)) = self.small.back() {
+            if back.eq(k) {
+                break;
+            }
+            self.small.remove(&back);
+            self.frequencies.insert(back, 0);
+            self.main.push_front(back, cost);
+        }
+    }
+
+    ///
+    /// Reinsert items from back of main queue with decreased frequency until `k` is at back
+    ///
+    fn rotate_main(&mut self, k: &u64) {
+        while let Some((back, cost)) = self.main.back() {
+            if back.eq(k) {
+                break;
+            }
+            self.main.remove(&back);
+            let frequency = self.frequency(&back).saturating_sub(1);
+            self.frequencies.insert(back, frequency);
+            self.main.push_front(back, cost);
+        }
+    }
+
+    ///
+    /// Forget the oldest ghosts until ghost queue fits its capacity
+    ///
+    fn trim_ghost(&mut self) {
+        while self.ghost.cost() > self.ghost_capacity && self.ghost.pop_back().is_some() {}
+    }
+}
+
+impl Policy for S3FifoPolicy {
+    fn insert(&mut self, k: u64, cost: usize) {
+        self.remove(&k);
+        if self.ghost.remove(&k).is_some() {
+            self.main.push_front(k, cost);
+        } else {
+            self.small.push_front(k, cost);
+        }
+        self.frequencies.insert(k, 0);
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        if self.small.set_cost(&k, cost) || self.main.set_cost(&k, cost) {
+            self.access(&k);
+        } else {
+            self.insert(k, cost);
+        }
+    }
+
+    fn access(&mut self, k: &u64) {
+        if let Some(frequency) = self.frequencies.get_mut(k) {
+            *frequency = (*frequency + 1).min(MAX_FREQUENCY);
+        }
+    }
+
+    fn remove(&mut self, k: &u64) {
+        if self.frequencies.remove(k).is_some() && self.small.remove(k).is_none() {
+            self.main.remove(k);
+        }
+    }
+
+    fn evict(&mut self, k: &u64) {
+        if self.small.get(k).is_some() {
+            self.rotate_small(k);
+            let cost = self.small.remove(k).expect("small item");
+            self.frequencies.remove(k);
+            self.ghost.push_front(*k, cost);
+            self.trim_ghost();
+        } else if self.main.get(k).is_some() {
+            self.rotate_main(k);
+            self.remove(k);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.small.clear();
+        self.main.clear();
+        self.ghost.clear();
+        self.frequencies.clear();
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        let victim = if self.evict_small() {
+            self.small_victim(exclude)
+                .or_else(|| self.main_victim(exclude))
+        } else {
+            None
+        };
+        victim
+            .or_else(|| self.main_victim(exclude))
+            .or_else(|| self.small.back_except(exclude))
+            .map(|(k, cost)| SampleItem::with_cost(k, admit.estimate(&k), cost))
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+
+    fn state(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("s3_fifo_small", self.small.cost()),
+            ("s3_fifo_main", self.main.cost()),
+            ("s3_fifo_ghost", self.ghost.cost()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::{Policy, S3FifoPolicy};
+    use crate::store::Item;
+    use crate::tiny_lfu::VoidTinyLFU;
+    use indexmap::IndexMap;
+
+    fn victim(policy: &S3FifoPolicy) -> u64 {
+        let data = IndexMap::<u64, Item<u64, u64>>::new();
+        policy.sample(&data, &VoidTinyLFU, &[]).unwrap().key
+    }
+
+    #[test]
+    fn small_capacity() {
+        assert_eq!(S3FifoPolicy::with_capacity(5).small_capacity(), 1);
+        assert_eq!(S3FifoPolicy::with_capacity(100).small_capacity(), 10);
+        assert_eq!(
+            S3FifoPolicy::with_small_percent(100, 20).small_capacity(),
+            20
+        );
+    }
+
+    #[test]
+    fn accessed_item_moves_to_main() {
+        let mut policy = S3FifoPolicy::with_capacity(10);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.access(&1);
+        //accessed item is skipped and moved into main queue on eviction
+        assert_eq!(victim(&policy), 2);
+        policy.evict(&2);
+        assert_eq!(
+            policy.state(),
+            vec![
+                ("s3_fifo_small", 0),
+                ("s3_fifo_main", 1),
+                ("s3_fifo_ghost", 1)
+            ]
+        );
+        //item from ghost queue is inserted into main queue
+        policy.insert(2, 1);
+        assert_eq!(policy.main.len(), 2);
+        assert_eq!(policy.ghost.len(), 0);
+    }
+
+    #[test]
+    fn main_reinsert() {
+        let mut policy = S3FifoPolicy::with_capacity(10);
+        for k in 1..=3 {
+            policy.insert(k, 1);
+            policy.access(&k);
+            policy.evict(&k);
+        }
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.insert(3, 1);
+        policy.access(&1);
+        policy.access(&1);
+        policy.access(&2);
+        assert_eq!(victim(&policy), 3);
+        policy.remove(&3);
+        //every item was accessed, so the one with the lowest frequency leaves first
+        assert_eq!(victim(&policy), 2);
+        policy.evict(&2);
+        assert_eq!(policy.frequency(&1), 1);
+        assert_eq!(victim(&policy), 1);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+use rand::seq::index;
+use rand::thread_rng;
+
+///
+/// How many samples are check to find one with lowest estimate
+///
+pub const SAMPLES_NUM: usize = 5;
+
+///
+/// Policy without any order of items.
+///
+/// Victim is item with lowest estimate from random sample of items.
+/// Sampled items are distinct, so every item is checked if storage has at most `SAMPLES_NUM` candidates.
+///
+#[derive(Clone, Debug, Default)]
+pub struct SampledPolicy;
+
+impl Policy for SampledPolicy {
+    const TRACKS_ACCESS: bool = false;
+
+    fn insert(&mut self, _k: u64, _cost: usize) {}
+
+    fn update(&mut self, _k: u64, _cost: usize) {}
+
+    fn access(&mut self, _k: &u64) {}
+
+    fn remove(&mut self, _k: &u64) {}
+
+    fn clear(&mut self) {}
+
+    fn sample<K, V>(
+        &self,
+        data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        let candidates = data.len() - exclude.iter().filter(|k| data.contains_key(*k)).count();
+        if candidates == 0 {
+            return None;
+        }
+        //sample distinct items, excluded ones are skipped after sampling
+        let amount = (SAMPLES_NUM + data.len() - candidates).min(data.len());
+        let mut result: Option<SampleItem> = None;
+        let indexes = index::sample(&mut thread_rng(), data.len(), amount);
+        for index in indexes
+            .into_iter()
+            .filter(|index| !exclude.contains(data.get_index(*index).expect("sample item").0))
+            .take(SAMPLES_NUM)
+        {
+            let (k, item) = data.get_index(index).expect("sample item");
+            let estimate = admit.estimate(k);
+            let sample = SampleItem::with_cost(*k, estimate, item.cost);
+            if let Some(current) = &result {
+                if sample.estimate.lt(&current.estimate) {
+                    result = Some(sample);
+                }
+            } else {
+                result = Some(sample)
+            }
+        }
+        result
+    }
+}
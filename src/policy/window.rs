@@ -0,0 +1,259 @@
+use crate::policy::list::OrderList;
+use crate::policy::Policy;
+use crate::store::{Item, SampleItem};
+use crate::tiny_lfu::TinyLFU;
+use indexmap::IndexMap;
+
+///
+/// Default share of admission window in percents of capacity
+///
+pub const DEFAULT_WINDOW_PERCENT: usize = 1;
+
+///
+/// Default share of protected segment in percents of main space
+///
+pub const DEFAULT_PROTECTED_PERCENT: usize = 80;
+
+///
+/// W-TinyLFU policy.
+///
+/// New items are inserted into small LRU admission window. Main space is segmented LRU with probation and protected segment.
+/// Item from window is moved to probation segment when window is full.
+/// Item from probation segment is promoted into protected segment when it is accessed.
+///
+/// When storage is full, the oldest item from window (candidate) is compared with the oldest item from probation segment (victim)
+/// and TinyLFU estimate decide which one is evicted. Incoming items are always admitted into window.
+///
+#[derive(Clone, Debug)]
+pub struct WindowTinyLFUPolicy {
+    window: OrderList,
+    probation: OrderList,
+    protected: OrderList,
+    window_capacity: usize,
+    protected_capacity: usize,
+}
+
+impl WindowTinyLFUPolicy {
+    ///
+    /// Create new policy for storage with defined capacity.
+    /// Window has 1% of capacity and protected segment has 80% of main space.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_window_percent(capacity, DEFAULT_WINDOW_PERCENT)
+    }
+
+    ///
+    /// Create new policy for storage with defined capacity and share of admission window.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: capacity of storage
+    /// - `window_percent`: share of admission window in percents of capacity (at least 1 item)
+    ///
+    /// # Panic
+    ///
+    /// If `window_percent` > 100
+    ///
+    pub fn with_window_percent(capacity: usize, window_percent: usize) -> Self {
+        assert!(window_percent <= 100);
+        let window_capacity = (capacity * window_percent / 100).max(1);
+        let main_capacity = capacity.saturating_sub(window_capacity);
+        Self {
+            window: OrderList::new(),
+            probation: OrderList::new(),
+            protected: OrderList::new(),
+            window_capacity,
+            protected_capacity: main_capacity * DEFAULT_PROTECTED_PERCENT / 100,
+        }
+    }
+
+    ///
+    /// Returns capacity of admission window
+    ///
+    pub fn window_capacity(&self) -> usize {
+        self.window_capacity
+    }
+
+    ///
+    /// Move the oldest items from window into probation segment until window fits its capacity.
+    /// The newest item stays in window.
+    ///
+    fn shrink_window(&mut self) {
+        while self.window.cost() > self.window_capacity && self.window.len() > 1 {
+            let (k, cost) = self.window.pop_back().expect("window item");
+            self.probation.push_front(k, cost);
+        }
+    }
+
+    ///
+    /// Move the oldest items from protected segment into probation segment until protected segment fits its capacity.
+    ///
+    fn shrink_protected(&mut self) {
+        while self.protected.cost() > self.protected_capacity {
+            let (k, cost) = self.protected.pop_back().expect("protected item");
+            self.probation.push_front(k, cost);
+        }
+    }
+
+    ///
+    /// Returns the oldest not excluded item from main space
+    ///
+    fn main_victim(&self, exclude: &[u64]) -> Option<(u64, usize)> {
+        self.probation
+            .back_except(exclude)
+            .or_else(|| self.protected.back_except(exclude))
+    }
+}
+
+impl Policy for WindowTinyLFUPolicy {
+    fn insert(&mut self, k: u64, cost: usize) {
+        self.window.push_front(k, cost);
+        self.shrink_window();
+    }
+
+    fn update(&mut self, k: u64, cost: usize) {
+        if self.window.set_cost(&k, cost) {
+            self.shrink_window();
+        } else if self.probation.set_cost(&k, cost) || self.protected.set_cost(&k, cost) {
+            self.shrink_protected();
+        } else {
+            self.insert(k, cost);
+        }
+        self.access(&k);
+    }
+
+    fn access(&mut self, k: &u64) {
+        if self.window.move_to_front(k) {
+            return;
+        }
+        if let Some(cost) = self.probation.remove(k) {
+            self.protected.push_front(*k, cost);
+            self.shrink_protected();
+            return;
+        }
+        self.protected.move_to_front(k);
+    }
+
+    fn remove(&mut self, k: &u64) {
+        if self.window.remove(k).is_none() && self.probation.remove(k).is_none() {
+            self.protected.remove(k);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.window.clear();
+        self.probation.clear();
+        self.protected.clear();
+    }
+
+    fn sample<K, V>(
+        &self,
+        _data: &IndexMap<u64, Item<K, V>>,
+        admit: &impl TinyLFU,
+        exclude: &[u64],
+    ) -> Option<SampleItem> {
+        //candidate from window is moved into main space only if window is full
+        let candidate = if self.window.cost() >= self.window_capacity {
+            self.window.back_except(exclude)
+        } else {
+            None
+        };
+        let victim = self.main_victim(exclude);
+        let sample = |(k, cost): (u64, usize)| SampleItem::with_cost(k, admit.estimate(&k), cost);
+        match (candidate.map(sample), victim.map(sample)) {
+            (Some(candidate), Some(victim)) => {
+                if candidate.estimate > victim.estimate {
+                    Some(victim)
+                } else {
+                    Some(candidate)
+                }
+            }
+            (Some(candidate), None) => Some(candidate),
+            (None, Some(victim)) => Some(victim),
+            (None, None) => self.window.back_except(exclude).map(sample),
+        }
+    }
+
+    fn admit(&self, _incoming_estimate: i64, _victims: &[SampleItem]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::policy::list::OrderList;
+    use crate::policy::{Policy, WindowTinyLFUPolicy};
+    use crate::store::Item;
+    use crate::tiny_lfu::{TinyLFU, TinyLFUCache};
+    use indexmap::IndexMap;
+
+    fn data() -> IndexMap<u64, Item<u64, u64>> {
+        IndexMap::new()
+    }
+
+    fn keys(list: &OrderList) -> Vec<u64> {
+        list.iter_back().map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn window_capacity() {
+        assert_eq!(WindowTinyLFUPolicy::with_capacity(10).window_capacity(), 1);
+        assert_eq!(
+            WindowTinyLFUPolicy::with_capacity(1000).window_capacity(),
+            10
+        );
+        assert_eq!(
+            WindowTinyLFUPolicy::with_window_percent(1000, 20).window_capacity(),
+            200
+        );
+    }
+
+    #[test]
+    fn window_overflow_to_probation() {
+        let mut policy = WindowTinyLFUPolicy::with_capacity(10);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        assert_eq!(keys(&policy.window), vec![2]);
+        assert_eq!(keys(&policy.probation), vec![1]);
+    }
+
+    #[test]
+    fn promote_to_protected() {
+        let mut policy = WindowTinyLFUPolicy::with_capacity(10);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        policy.access(&1);
+        assert_eq!(keys(&policy.protected), vec![1]);
+        policy.remove(&1);
+        assert_eq!(policy.protected.len(), 0);
+    }
+
+    #[test]
+    fn candidate_wins() {
+        let mut policy = WindowTinyLFUPolicy::with_capacity(10);
+        let mut admit = TinyLFUCache::new(100);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        admit.increment(&2);
+        admit.increment(&2);
+        let victim = policy.sample(&data(), &admit, &[]).unwrap();
+        assert_eq!(victim.key, 1);
+    }
+
+    #[test]
+    fn victim_wins() {
+        let mut policy = WindowTinyLFUPolicy::with_capacity(10);
+        let mut admit = TinyLFUCache::new(100);
+        policy.insert(1, 1);
+        policy.insert(2, 1);
+        admit.increment(&1);
+        admit.increment(&1);
+        let victim = policy.sample(&data(), &admit, &[]).unwrap();
+        assert_eq!(victim.key, 2);
+        assert!(policy.admit(0, &[victim]));
+    }
+}
@@ -0,0 +1,165 @@
+///
+/// Max value of 4-bit counter
+///
+const MAX_COUNT: u64 = 15;
+
+///
+/// Number of 4-bit counters packed in one word
+///
+const COUNTERS_PER_WORD: usize = 16;
+
+///
+/// Mask which clears highest bit of every counter after word is shifted right by one bit
+///
+const RESET_MASK: u64 = 0x7777_7777_7777_7777;
+
+///
+/// Count-min sketch with 4-bit saturating counters packed into `u64` words.
+///
+/// Every row has own counter for item chosen by double hashing of item key.
+/// Estimate is the smallest counter of item over all rows.
+///
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrequencySketch {
+    table: Vec<u64>,
+    rows: usize,
+    width: usize,
+}
+
+impl FrequencySketch {
+    ///
+    /// Create new sketch with zero counters
+    ///
+    /// # Arguments
+    ///
+    /// - `rows`: number of rows
+    /// - `width`: min number of counters in every row, rounded up to power of two and at least 16
+    ///
+    /// # Panic
+    ///
+    /// If `rows` is 0.
+    ///
+    pub fn new(rows: usize, width: usize) -> Self {
+        assert_ne!(rows, 0);
+        let width = width.max(COUNTERS_PER_WORD).next_power_of_two();
+        Self {
+            table: vec![0; rows * width / COUNTERS_PER_WORD],
+            rows,
+            width,
+        }
+    }
+
+    ///
+    /// Returns estimated count of item, which is never lower than real count up to 15
+    ///
+    pub fn estimate(&self, k: &u64) -> u64 {
+        self.positions(*k)
+            .map(|pos| self.counter(pos))
+            .min()
+            .unwrap_or(0)
+    }
+
+    ///
+    /// Increment every counter of item which is not saturated yet
+    ///
+    pub fn increment(&mut self, k: &u64) {
+        for pos in self.positions(*k) {
+            if self.counter(pos) < MAX_COUNT {
+                self.table[pos / COUNTERS_PER_WORD] += 1 << Self::shift(pos);
+            }
+        }
+    }
+
+    ///
+    /// Halve all counters
+    ///
+    pub fn halve(&mut self) {
+        for word in self.table.iter_mut() {
+            *word = (*word >> 1) & RESET_MASK;
+        }
+    }
+
+    ///
+    /// Set all counters to zero
+    ///
+    pub fn clear(&mut self) {
+        for word in self.table.iter_mut() {
+            *word = 0;
+        }
+    }
+
+    ///
+    /// Returns position of counter of item in every row
+    ///
+    fn positions(&self, k: u64) -> impl Iterator<Item = usize> {
+        let first = mix(k);
+        //odd step visits every counter in row with power of two width
+        let step = mix(first) | 1;
+        let width = self.width;
+        (0..self.rows).map(move |row| {
+            let idx = first.wrapping_add((row as u64).wrapping_mul(step)) as usize & (width - 1);
+            row * width + idx
+        })
+    }
+
+    fn counter(&self, pos: usize) -> u64 {
+        (self.table[pos / COUNTERS_PER_WORD] >> Self::shift(pos)) & MAX_COUNT
+    }
+
+    fn shift(pos: usize) -> u64 {
+        ((pos % COUNTERS_PER_WORD) * 4) as u64
+    }
+}
+
+///
+/// Spread bits of key hash (finalizer of splitmix64)
+///
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sketch::FrequencySketch;
+
+    #[test]
+    fn increment_and_saturate() {
+        let mut sketch = FrequencySketch::new(4, 64);
+        assert_eq!(sketch.estimate(&1), 0);
+        for _ in 0..3 {
+            sketch.increment(&1);
+        }
+        assert_eq!(sketch.estimate(&1), 3);
+        for _ in 0..20 {
+            sketch.increment(&1);
+        }
+        assert_eq!(sketch.estimate(&1), 15);
+        assert_eq!(sketch.table.len(), 16);
+    }
+
+    #[test]
+    fn halve_and_clear() {
+        let mut sketch = FrequencySketch::new(4, 64);
+        for _ in 0..15 {
+            sketch.increment(&1);
+        }
+        for _ in 0..5 {
+            sketch.increment(&2);
+        }
+        sketch.halve();
+        assert_eq!(sketch.estimate(&1), 7);
+        assert_eq!(sketch.estimate(&2), 2);
+        sketch.clear();
+        assert_eq!(sketch.estimate(&1), 0);
+    }
+
+    #[test]
+    fn width() {
+        let sketch = FrequencySketch::new(3, 100);
+        assert_eq!(sketch.width, 128);
+        assert_eq!(sketch.table.len(), 24);
+        assert_eq!(FrequencySketch::new(1, 1).width, 16);
+    }
+}
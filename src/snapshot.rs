@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+///
+/// Version of snapshot format written by this crate
+///
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+///
+/// Version of admission state format written by this crate
+///
+pub const ADMISSION_VERSION: u32 = 1;
+
+///
+/// Header of exported admission state, which is checked before state is decoded
+///
+#[derive(Serialize, Deserialize)]
+pub struct AdmissionHeader {
+    pub version: u32,
+    pub window_size: u64,
+}
+
+///
+/// Saved item of cache
+///
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotItem<K, V> {
+    pub k: K,
+    pub v: V,
+    pub cost: usize,
+
+    ///
+    /// Remaining time to live when snapshot was saved
+    ///
+    pub ttl: Option<Duration>,
+    pub time_to_idle: Option<Duration>,
+
+    ///
+    /// Remaining time without access when snapshot was saved
+    ///
+    pub idle: Option<Duration>,
+}
+
+///
+/// Saved contents of cache with optional admission state.
+///
+/// Admission state is valid only with hasher of saved cache, so they are saved together.
+///
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot<K, V, H, A> {
+    ///
+    /// Wall-clock time of save as duration since unix epoch
+    ///
+    pub saved_at: Duration,
+    pub items: Vec<SnapshotItem<K, V>>,
+    pub admission: Option<(H, A)>,
+}
+
+///
+/// Returns actual wall-clock time as duration since unix epoch.
+///
+/// Monotonic time of cache clock can not be compared between processes, so wall-clock is used to measure downtime.
+///
+pub fn since_epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
@@ -1,19 +1,16 @@
-use crate::cache::OnEvict;
+use crate::cache::{OnEvict, RemovalCause};
+use crate::clock::{Clock, MonotonicClock};
+use crate::policy::{Policy, SampledPolicy};
 use crate::tiny_lfu::TinyLFU;
-use crate::ttl::{Expiration, ExpirationMap};
-use indexmap::map::{IndexMap, Keys};
+use crate::ttl::{Expiration, TimerWheel};
+use indexmap::map::{self, IndexMap};
 use log::warn;
-use rand::distributions::Uniform;
-use rand::{thread_rng, Rng};
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
-use std::time::{Duration, SystemTime};
-
-///
-/// How many samples are check to find one with lowest estimate
-///
-pub const SAMPLES_NUM: usize = 5;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 ///
 /// SampleItem hold info about item and its estimate in TinyLFU
@@ -29,6 +26,11 @@ pub struct SampleItem {
     /// Item estimate from TinyFLF
     ///
     pub estimate: i64,
+
+    ///
+    /// Item cost in storage
+    ///
+    pub cost: usize,
 }
 
 impl SampleItem {
@@ -41,15 +43,30 @@ impl SampleItem {
     /// - `estimate`: item estimate
     ///
     pub fn new(key: u64, estimate: i64) -> Self {
-        Self { key, estimate }
+        Self::with_cost(key, estimate, 1)
+    }
+
+    ///
+    /// New Sample item with defined cost
+    ///
+    /// # Arguments
+    ///
+    /// - `key`: item identification
+    /// - `estimate`: item estimate
+    /// - `cost`: item cost in storage
+    ///
+    pub fn with_cost(key: u64, estimate: i64, cost: usize) -> Self {
+        Self {
+            key,
+            estimate,
+            cost,
+        }
     }
 }
 
 impl PartialOrd for SampleItem {
     fn partial_cmp(&self, other: &SampleItem) -> Option<Ordering> {
-        self.estimate
-            .partial_cmp(&other.estimate)
-            .map(|ord| ord.then(self.key.cmp(&other.key)))
+        Some(self.cmp(other))
     }
 }
 
@@ -75,6 +92,68 @@ impl Hash for SampleItem {
     }
 }
 
+///
+/// Value which marks item without expiration time
+///
+const NO_EXPIRATION: u64 = u64::MAX;
+
+///
+/// Expiration time of item, which can be moved also through shared reference.
+///
+/// Time is hold as offset in nanoseconds from time when item was inserted into storage.
+///
+#[derive(Debug, Default)]
+struct ExpirationTime {
+    anchor: Option<Instant>,
+    offset: AtomicU64,
+}
+
+impl ExpirationTime {
+    ///
+    /// Create expiration time of item inserted at given time
+    ///
+    fn new(now: Instant, expiration_time: Option<Instant>) -> Self {
+        let result = Self {
+            anchor: Some(now),
+            offset: AtomicU64::new(NO_EXPIRATION),
+        };
+        result.set(expiration_time);
+        result
+    }
+
+    fn get(&self) -> Option<Instant> {
+        let offset = self.offset.load(atomic::Ordering::Acquire);
+        if offset == NO_EXPIRATION {
+            None
+        } else {
+            self.anchor
+                .map(|anchor| anchor + Duration::from_nanos(offset))
+        }
+    }
+
+    fn set(&self, expiration_time: Option<Instant>) {
+        let offset = match (self.anchor, expiration_time) {
+            (Some(anchor), Some(expiration_time)) => expiration_time
+                .saturating_duration_since(anchor)
+                .as_nanos()
+                .min(NO_EXPIRATION as u128 - 1)
+                as u64,
+            _ => NO_EXPIRATION,
+        };
+        self.offset.store(offset, atomic::Ordering::Release);
+    }
+}
+
+///
+/// Returns earlier from two optional times
+///
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
 ///
 /// Storage item with hold item key, value and optionally expiration time
 ///
@@ -83,7 +162,22 @@ pub struct Item<K, V> {
     ///
     /// Expiration time
     ///
-    pub expiration_time: Option<SystemTime>,
+    expiration_time: ExpirationTime,
+
+    ///
+    /// Expiration time defined by time to live, which is not moved by access
+    ///
+    ttl_expiration_time: ExpirationTime,
+
+    ///
+    /// Item expires when it is not accessed for this time
+    ///
+    pub time_to_idle: Option<Duration>,
+
+    ///
+    /// Shorter time to idle used only when item is scheduled, e.g. for item restored from snapshot
+    ///
+    idle_remaining: Option<Duration>,
 
     ///
     /// Item key
@@ -94,11 +188,16 @@ pub struct Item<K, V> {
     /// Item value
     ///
     pub v: V,
+
+    ///
+    /// Item cost in storage
+    ///
+    pub cost: usize,
 }
 
 impl<K, V> Item<K, V> {
     ///
-    /// New storage item without expiration time
+    /// New storage item without expiration time with cost 1
     ///
     /// # Arguments
     ///
@@ -106,12 +205,159 @@ impl<K, V> Item<K, V> {
     /// - `v`: item value
     ///
     pub fn new(k: K, v: V) -> Self {
+        Self::with_cost(k, v, 1)
+    }
+
+    ///
+    /// New storage item without expiration time with defined cost
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item key
+    /// - `v`: item value
+    /// - `cost`: item cost in storage
+    ///
+    pub fn with_cost(k: K, v: V, cost: usize) -> Self {
         Self {
-            expiration_time: None,
+            expiration_time: ExpirationTime::default(),
+            ttl_expiration_time: ExpirationTime::default(),
+            time_to_idle: None,
+            idle_remaining: None,
             k,
             v,
+            cost,
         }
     }
+
+    ///
+    /// Set time after last access when item expires. Zero duration means no time to idle.
+    ///
+    /// # Arguments
+    ///
+    /// - `time_to_idle`: how long can be item in storage without access
+    ///
+    pub fn with_time_to_idle(mut self, time_to_idle: Duration) -> Self {
+        self.time_to_idle = Some(time_to_idle).filter(|idle| !idle.is_zero());
+        self
+    }
+
+    ///
+    /// Set time without access after which item expires for the first time, e.g. rest of idle time of restored item.
+    /// Every access moves expiration by full time to idle.
+    ///
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_idle_remaining(mut self, idle_remaining: Duration) -> Self {
+        self.idle_remaining = Some(idle_remaining);
+        self
+    }
+
+    ///
+    /// Returns time when item expires
+    ///
+    pub fn expiration_time(&self) -> Option<Instant> {
+        self.expiration_time.get()
+    }
+
+    ///
+    /// Returns time when item expires because of its time to live, regardless of time to idle
+    ///
+    pub fn ttl_expiration_time(&self) -> Option<Instant> {
+        self.ttl_expiration_time.get()
+    }
+
+    ///
+    /// Returns true if item has expiration time before given time
+    ///
+    /// # Arguments
+    ///
+    /// - `now`: actual time
+    ///
+    pub fn is_expired(&self, now: &Instant) -> bool {
+        self.expiration_time()
+            .is_some_and(|expiration_time| now.gt(&expiration_time))
+    }
+
+    ///
+    /// Set expiration time of item inserted into storage at given time.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    /// - `now`: actual time
+    /// - `expiration`: time to live of item, zero duration means no time to live
+    /// - `previous`: expiration time of replaced item with the same identification
+    /// - `timer`: expiration times of all items in storage
+    ///
+    pub fn schedule(
+        &mut self,
+        k: u64,
+        now: &Instant,
+        expiration: Duration,
+        previous: Option<Instant>,
+        timer: &mut impl Expiration,
+    ) {
+        let ttl_expiration_time = Some(*now + expiration).filter(|_| !expiration.is_zero());
+        self.ttl_expiration_time = ExpirationTime::new(*now, ttl_expiration_time);
+        let idle_remaining = self.idle_remaining.take();
+        let idle_expiration_time = self
+            .time_to_idle
+            .map(|time_to_idle| *now + idle_remaining.unwrap_or(time_to_idle));
+        let expiration = earliest(ttl_expiration_time, idle_expiration_time)
+            .map_or(Duration::from_secs(0), |expiration_time| {
+                expiration_time - *now
+            });
+        let expiration_time = match previous {
+            Some(expiration_time) => timer.update(k, &expiration_time, now, expiration),
+            None => timer.insert(k, now, expiration),
+        };
+        self.expiration_time = ExpirationTime::new(*now, expiration_time);
+    }
+
+    ///
+    /// Move expiration time of idle item after access. Hard time to live is never exceeded.
+    ///
+    pub fn touch(&self, k: &u64, now: &Instant, expiration: &mut impl Expiration) {
+        let time_to_idle = match self.time_to_idle {
+            Some(time_to_idle) => time_to_idle,
+            None => return,
+        };
+        let old_expiration_time = match self.expiration_time() {
+            Some(expiration_time) => expiration_time,
+            None => return,
+        };
+        let expiration_time = earliest(Some(*now + time_to_idle), self.ttl_expiration_time.get())
+            .expect("idle expiration time");
+        if expiration_time > old_expiration_time {
+            let expiration_time =
+                expiration.update(*k, &old_expiration_time, now, expiration_time - *now);
+            self.expiration_time.set(expiration_time);
+        }
+    }
+
+    ///
+    /// Set new time to live of item measured from given time. Zero duration means no time to live.
+    /// Idle item expires at the latest after the new time to live.
+    ///
+    pub fn expire_after(
+        &self,
+        k: &u64,
+        now: &Instant,
+        expiration: Duration,
+        timer_wheel: &mut impl Expiration,
+    ) {
+        let ttl_expiration_time = Some(*now + expiration).filter(|_| !expiration.is_zero());
+        self.ttl_expiration_time.set(ttl_expiration_time);
+        let idle_expiration_time = self.time_to_idle.map(|time_to_idle| *now + time_to_idle);
+        let expiration = earliest(ttl_expiration_time, idle_expiration_time)
+            .map_or(Duration::from_secs(0), |expiration_time| {
+                expiration_time - *now
+            });
+        let expiration_time = match self.expiration_time() {
+            Some(expiration_time) => timer_wheel.update(*k, &expiration_time, now, expiration),
+            None => timer_wheel.insert(*k, now, expiration),
+        };
+        self.expiration_time.set(expiration_time);
+    }
 }
 
 impl<K, V> Deref for Item<K, V> {
@@ -123,11 +369,43 @@ impl<K, V> Deref for Item<K, V> {
 }
 
 ///
-/// Storage supported functions
+/// Storage supported functions.
 ///
-pub trait Store<K, V>: Iterator {
+/// Items are identified by hash of their key. Store is responsible for expiration of items,
+/// which can be kept by `Item::schedule`, `Item::touch` and `Item::expire_after` with any `Expiration`.
+/// Implementation can be checked by `conformance::store`.
+///
+pub trait Store<K, V> {
+    ///
+    /// Iterator over keys of all items in storage
+    ///
+    type Keys<'a>: Iterator<Item = &'a u64>
+    where
+        Self: 'a;
+
     ///
-    /// Returns how many items can be hold in storage
+    /// Iterator over mutable refs of all items in storage
+    ///
+    type ValuesMut<'a>: Iterator<Item = &'a mut Item<K, V>>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    ///
+    /// Iterator over items removed from storage
+    ///
+    type Drain<'a>: Iterator<Item = Item<K, V>>
+    where
+        Self: 'a;
+
+    ///
+    /// Iterator over items of consumed storage
+    ///
+    type IntoValues: Iterator<Item = Item<K, V>>;
+
+    ///
+    /// Returns max total cost of items which can be hold in storage
     ///
     fn capacity(&self) -> usize;
 
@@ -144,7 +422,12 @@ pub trait Store<K, V>: Iterator {
     }
 
     ///
-    /// Returns how many room left
+    /// Returns total cost of items in storage
+    ///
+    fn cost(&self) -> usize;
+
+    ///
+    /// Returns how many room (cost) left
     ///
     fn room_left(&self) -> usize;
 
@@ -158,12 +441,17 @@ pub trait Store<K, V>: Iterator {
     fn contains(&self, k: &u64) -> bool;
 
     ///
-    /// Return an iterator over the keys of the map, in their order
+    /// Returns actual time of storage clock
+    ///
+    fn now(&self) -> Instant;
+
+    ///
+    /// Return an iterator over the keys of all items in storage, including expired ones.
     ///
-    fn keys(&self) -> Keys<u64, Item<K, V>>;
+    fn keys(&self) -> Self::Keys<'_>;
 
     ///
-    /// Return item ref if is in storage
+    /// Return item ref if is in storage. Item is marked as accessed.
     ///
     /// # Arguments
     ///
@@ -172,7 +460,16 @@ pub trait Store<K, V>: Iterator {
     fn get(&self, k: &u64) -> Option<&Item<K, V>>;
 
     ///
-    /// Return mutable item ref if is in storage
+    /// Return item ref if is in storage without marking item as accessed.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn peek(&self, k: &u64) -> Option<&Item<K, V>>;
+
+    ///
+    /// Return mutable item ref if is in storage. Item is marked as accessed.
     ///
     /// # Arguments
     ///
@@ -180,6 +477,50 @@ pub trait Store<K, V>: Iterator {
     ///
     fn get_mut(&mut self, k: &u64) -> Option<&mut Item<K, V>>;
 
+    ///
+    /// Return mutable item ref if is in storage without marking item as accessed.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn peek_mut(&mut self, k: &u64) -> Option<&mut Item<K, V>>;
+
+    ///
+    /// Return item ref if is in storage, even if it is already expired. Item is not marked as accessed.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn peek_raw(&self, k: &u64) -> Option<&Item<K, V>>;
+
+    ///
+    /// Return mutable item ref if is in storage, even if it is already expired. Item is not marked as accessed.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn peek_raw_mut(&mut self, k: &u64) -> Option<&mut Item<K, V>>;
+
+    ///
+    /// Return an iterator over mutable refs of all items in storage, including expired ones.
+    /// Items are not marked as accessed.
+    ///
+    fn values_mut(&mut self) -> Self::ValuesMut<'_>;
+
+    ///
+    /// Set new time to live of item measured from actual time. Zero duration means no time to live.
+    /// Item is not marked as accessed.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    /// - `expiration`: how long should item lives
+    ///
+    fn set_ttl(&self, k: &u64, expiration: Duration);
+
     ///
     /// Insert item into storage. Returns preview item if exists with given key.
     ///
@@ -193,13 +534,13 @@ pub trait Store<K, V>: Iterator {
     }
 
     ///
-    /// Insert item into storage with defined time to life in seconds. Returns preview item if exists with given key.
+    /// Insert item into storage with defined time to life. Returns preview item if exists with given key.
     ///
     /// # Arguments
     ///
     /// - `k`: item identification
     /// - `item`: storage item
-    /// - `expiration`: how many seconds should item lives
+    /// - `expiration`: how long should item lives
     ///
     fn insert_with_ttl(
         &mut self,
@@ -217,6 +558,18 @@ pub trait Store<K, V>: Iterator {
     ///
     fn remove(&mut self, k: &u64) -> Option<Item<K, V>>;
 
+    ///
+    /// Remove and return victim selected to make room for new items.
+    /// By default eviction is the same as removal.
+    ///
+    /// # Arguments
+    ///
+    /// - `k`: item identification
+    ///
+    fn evict(&mut self, k: &u64) -> Option<Item<K, V>> {
+        self.remove(k)
+    }
+
     ///
     /// Remove all expired items from storage.
     /// Call `on_evict` for every removed item.
@@ -231,48 +584,144 @@ pub trait Store<K, V>: Iterator {
     fn clear(&mut self);
 
     ///
-    /// If storage contains any items, than return one item with lowest estimate from checked sample.
+    /// Remove all items from storage and return them in iterator, including expired ones.
+    ///
+    fn drain(&mut self) -> Self::Drain<'_>;
+
+    ///
+    /// Consume storage and return iterator over all items, including expired ones.
+    ///
+    fn into_values(self) -> Self::IntoValues
+    where
+        Self: Sized;
+
+    ///
+    /// If storage contains any not excluded items, than return one item with lowest estimate from checked sample.
     ///
     /// # Arguments
     ///
     /// - `admit`: TinyLFU for calculating estimate of item in storage.
+    /// - `exclude`: items which can not be returned as sample.
+    ///
+    fn sample(&self, admit: &impl TinyLFU, exclude: &[u64]) -> Option<SampleItem>;
+
+    ///
+    /// Decide if incoming item should be admitted at the expense of victims.
+    ///
+    /// # Arguments
+    ///
+    /// - `incoming_estimate`: estimate of incoming item
+    /// - `victims`: victims which are removed if incoming item is admitted
     ///
-    fn sample(&self, admit: &impl TinyLFU) -> Option<SampleItem>;
+    fn admit(&self, incoming_estimate: i64, victims: &[SampleItem]) -> bool;
+
+    ///
+    /// Returns internal state of eviction policy as named values, e.g. target size of adaptive segment.
+    /// By default storage has no state to report.
+    ///
+    fn policy_state(&self) -> Vec<(&'static str, usize)> {
+        Vec::new()
+    }
 }
 
 ///
 /// Basic implementation of storage for cache.
 ///
 /// Data are hold in IndexMap which allow to create sample set.
+/// Order of items and victims selection is managed by eviction policy.
 ///
-pub struct Storage<K, V> {
+pub struct Storage<K, V, P = SampledPolicy, C = MonotonicClock> {
     data: IndexMap<u64, Item<K, V>>,
-    expiration_map: ExpirationMap,
+    timer_wheel: Mutex<TimerWheel>,
+    policy: Mutex<P>,
+    clock: C,
     capacity: usize,
+    cost: usize,
 }
 
 impl<K, V> Storage<K, V> {
     ///
-    /// Create new storage with defined capacity.
+    /// Create new storage with defined capacity (max total cost of items).
+    /// Victims are selected from random sample of items.
     ///
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_policy(capacity, SampledPolicy)
+    }
+}
+
+impl<K, V, P> Storage<K, V, P>
+where
+    P: Policy,
+{
+    ///
+    /// Create new storage with defined capacity (max total cost of items) and eviction policy.
+    ///
+    pub fn with_policy(capacity: usize, policy: P) -> Self {
+        Self::with_clock(capacity, policy, MonotonicClock)
+    }
+}
+
+impl<K, V, P, C> Storage<K, V, P, C>
+where
+    P: Policy,
+    C: Clock,
+{
+    ///
+    /// Create new storage with defined capacity (max total cost of items), eviction policy and clock for expiration of items.
+    ///
+    pub fn with_clock(capacity: usize, policy: P, clock: C) -> Self {
         Self {
             capacity,
+            cost: 0,
             data: IndexMap::new(),
-            expiration_map: ExpirationMap::new(),
+            timer_wheel: Mutex::new(TimerWheel::new(clock.now())),
+            policy: Mutex::new(policy),
+            clock,
         }
     }
 }
 
-impl<K, V> Iterator for Storage<K, V> {
-    type Item = Item<K, V>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        unimplemented!()
+impl<K, V, P, C> Storage<K, V, P, C> {
+    ///
+    /// Remove item from data and expiration, but not from policy
+    ///
+    fn take(&mut self, k: &u64) -> Option<Item<K, V>> {
+        let item = self.data.remove(k)?;
+        if let Some(expiration_time) = &item.expiration_time() {
+            self.timer_wheel
+                .get_mut()
+                .unwrap()
+                .remove(k, expiration_time);
+        }
+        self.cost -= item.cost;
+        Some(item)
     }
 }
 
-impl<K, V> Store<K, V> for Storage<K, V> {
+impl<K, V, P, C> Store<K, V> for Storage<K, V, P, C>
+where
+    P: Policy,
+    C: Clock,
+{
+    type Keys<'a>
+        = map::Keys<'a, u64, Item<K, V>>
+    where
+        Self: 'a;
+
+    type ValuesMut<'a>
+        = map::ValuesMut<'a, u64, Item<K, V>>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    type Drain<'a>
+        = std::iter::Map<map::Drain<'a, u64, Item<K, V>>, fn((u64, Item<K, V>)) -> Item<K, V>>
+    where
+        Self: 'a;
+
+    type IntoValues = map::IntoValues<u64, Item<K, V>>;
+
     fn capacity(&self) -> usize {
         self.capacity
     }
@@ -281,47 +730,74 @@ impl<K, V> Store<K, V> for Storage<K, V> {
         self.data.len()
     }
 
+    fn cost(&self) -> usize {
+        self.cost
+    }
+
     fn room_left(&self) -> usize {
-        self.capacity() - self.len()
+        self.capacity().saturating_sub(self.cost())
     }
 
     fn contains(&self, k: &u64) -> bool {
-        self.get(k).is_some()
+        self.peek(k).is_some()
     }
 
-    fn keys(&self) -> Keys<u64, Item<K, V>> {
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    fn keys(&self) -> Self::Keys<'_> {
         self.data.keys()
     }
 
     fn get(&self, k: &u64) -> Option<&Item<K, V>> {
-        if let Some(item) = self.data.get(k) {
-            if let Some(expiration_time) = &item.expiration_time {
-                if SystemTime::now().gt(expiration_time) {
-                    None
-                } else {
-                    Some(item)
-                }
-            } else {
-                Some(item)
-            }
-        } else {
-            None
+        let item = self.peek(k)?;
+        if item.time_to_idle.is_some() {
+            let mut timer_wheel = self.timer_wheel.lock().unwrap();
+            item.touch(k, &self.clock.now(), &mut *timer_wheel);
         }
+        if P::TRACKS_ACCESS {
+            self.policy.lock().unwrap().access(k);
+        }
+        Some(item)
+    }
+
+    fn peek(&self, k: &u64) -> Option<&Item<K, V>> {
+        let now = self.clock.now();
+        self.data.get(k).filter(|item| !item.is_expired(&now))
     }
 
     fn get_mut(&mut self, k: &u64) -> Option<&mut Item<K, V>> {
-        if let Some(item) = self.data.get_mut(k) {
-            if let Some(expiration_time) = &item.expiration_time {
-                if SystemTime::now().gt(expiration_time) {
-                    None
-                } else {
-                    Some(item)
-                }
-            } else {
-                Some(item)
-            }
-        } else {
-            None
+        let now = self.clock.now();
+        let item = self.data.get_mut(k).filter(|item| !item.is_expired(&now))?;
+        item.touch(k, &now, self.timer_wheel.get_mut().unwrap());
+        if P::TRACKS_ACCESS {
+            self.policy.get_mut().unwrap().access(k);
+        }
+        Some(item)
+    }
+
+    fn peek_mut(&mut self, k: &u64) -> Option<&mut Item<K, V>> {
+        let now = self.clock.now();
+        self.data.get_mut(k).filter(|item| !item.is_expired(&now))
+    }
+
+    fn peek_raw(&self, k: &u64) -> Option<&Item<K, V>> {
+        self.data.get(k)
+    }
+
+    fn peek_raw_mut(&mut self, k: &u64) -> Option<&mut Item<K, V>> {
+        self.data.get_mut(k)
+    }
+
+    fn values_mut(&mut self) -> Self::ValuesMut<'_> {
+        self.data.values_mut()
+    }
+
+    fn set_ttl(&self, k: &u64, expiration: Duration) {
+        if let Some(item) = self.peek(k) {
+            let mut timer_wheel = self.timer_wheel.lock().unwrap();
+            item.expire_after(k, &self.clock.now(), expiration, &mut *timer_wheel);
         }
     }
 
@@ -331,39 +807,49 @@ impl<K, V> Store<K, V> for Storage<K, V> {
         mut item: Item<K, V>,
         expiration: Duration,
     ) -> Option<Item<K, V>> {
-        let old_item = if let Some(old_item) = self.data.remove(&k) {
-            if let Some(expiration_time) = &old_item.expiration_time {
-                self.expiration_map.remove(&k, expiration_time);
-            }
-            Some(old_item)
+        let old_item = self.data.remove(&k);
+        let now = self.clock.now();
+        item.schedule(
+            k,
+            &now,
+            expiration,
+            old_item.as_ref().and_then(|old| old.expiration_time()),
+            self.timer_wheel.get_mut().unwrap(),
+        );
+        let policy = self.policy.get_mut().unwrap();
+        if let Some(old_item) = &old_item {
+            self.cost -= old_item.cost;
+            policy.update(k, item.cost);
         } else {
-            None
-        };
-        item.expiration_time = self.expiration_map.insert(k, expiration);
+            policy.insert(k, item.cost);
+        }
+        self.cost += item.cost;
         self.data.insert(k, item);
         old_item
     }
 
     fn remove(&mut self, k: &u64) -> Option<Item<K, V>> {
-        if let Some(item) = self.data.remove(k) {
-            if let Some(expiration_time) = &item.expiration_time {
-                self.expiration_map.remove(k, expiration_time);
-            }
-            Some(item)
-        } else {
-            None
-        }
+        let item = self.take(k)?;
+        self.policy.get_mut().unwrap().remove(k);
+        Some(item)
+    }
+
+    fn evict(&mut self, k: &u64) -> Option<Item<K, V>> {
+        let item = self.take(k)?;
+        self.policy.get_mut().unwrap().evict(k);
+        Some(item)
     }
 
     fn cleanup<E>(&mut self, on_evict: &Option<E>)
     where
         E: OnEvict<K, V>,
     {
-        let now = SystemTime::now();
-        let keys = self.expiration_map.cleanup(&now);
+        let timer_wheel = self.timer_wheel.get_mut().unwrap();
+        let now = self.clock.now();
+        let keys = timer_wheel.cleanup(&now);
         for k in keys {
             if let Some(item) = self.data.get(&k) {
-                if let Some(expiration_time) = &item.expiration_time {
+                if let Some(expiration_time) = &item.expiration_time() {
                     if now.lt(expiration_time) {
                         warn!("Expiration map contains invalid expiration time for item!");
                         continue;
@@ -378,43 +864,53 @@ impl<K, V> Store<K, V> for Storage<K, V> {
             }
             let item = self.remove(&k).unwrap();
             if let Some(on_evict) = on_evict {
-                on_evict.evict(&item.k, &item.v);
+                on_evict.evict(item.k, item.v, RemovalCause::Expired);
             }
         }
     }
 
     fn clear(&mut self) {
-        self.expiration_map.clear();
+        self.timer_wheel.get_mut().unwrap().clear();
         self.data.clear();
+        self.policy.get_mut().unwrap().clear();
+        self.cost = 0;
     }
 
-    fn sample(&self, admit: &impl TinyLFU) -> Option<SampleItem> {
-        if self.is_empty() {
-            return None;
-        }
-        let items_range = Uniform::new(0_usize, self.len());
-        let mut generator = thread_rng().sample_iter(items_range);
-        let mut result: Option<SampleItem> = None;
-        for _ in 0..SAMPLES_NUM {
-            let index = generator.next().unwrap();
-            let (k, _) = self.data.get_index(index).expect("sample item");
-            let estimate = admit.estimate(&k);
-            let sample = SampleItem::new(*k, estimate);
-            if let Some(current) = &result {
-                if sample.estimate.lt(&current.estimate) {
-                    result = Some(sample);
-                }
-            } else {
-                result = Some(sample)
-            }
-        }
-        result
+    fn drain(&mut self) -> Self::Drain<'_> {
+        self.timer_wheel.get_mut().unwrap().clear();
+        self.policy.get_mut().unwrap().clear();
+        self.cost = 0;
+        self.data.drain(..).map(|(_, item)| item)
+    }
+
+    fn into_values(self) -> Self::IntoValues {
+        self.data.into_values()
+    }
+
+    fn sample(&self, admit: &impl TinyLFU, exclude: &[u64]) -> Option<SampleItem> {
+        self.policy
+            .lock()
+            .unwrap()
+            .sample(&self.data, admit, exclude)
+    }
+
+    fn admit(&self, incoming_estimate: i64, victims: &[SampleItem]) -> bool {
+        self.policy
+            .lock()
+            .unwrap()
+            .admit(incoming_estimate, victims)
+    }
+
+    fn policy_state(&self) -> Vec<(&'static str, usize)> {
+        self.policy.lock().unwrap().state()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cache::OnEvict;
+    use crate::cache::{OnEvict, RemovalCause};
+    use crate::clock::ManualClock;
+    use crate::policy::SampledPolicy;
     use crate::store::{Item, SampleItem, Storage, Store};
     use crate::tiny_lfu::{TinyLFU, TinyLFUCache};
     use std::cmp::Ordering;
@@ -426,7 +922,7 @@ mod tests {
     struct Evict {}
 
     impl<K, V> OnEvict<K, V> for Evict {
-        fn evict(&self, _k: &K, _v: &V) {}
+        fn evict(&self, _k: K, _v: V, _cause: RemovalCause) {}
     }
 
     #[test]
@@ -450,6 +946,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn sample_eq() {
         let item_1 = SampleItem::new(1, 2);
         let item_2 = SampleItem::new(2, 1);
@@ -460,6 +957,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants, clippy::manual_hash_one)]
     fn hash() {
         let item_1 = SampleItem::new(1, 2);
         let mut state = RandomState::new().build_hasher();
@@ -477,11 +975,12 @@ mod tests {
 
     #[test]
     fn do_not_get_expired_items() {
-        let mut store = Storage::with_capacity(10);
+        let clock = ManualClock::new();
+        let mut store = Storage::with_clock(10, SampledPolicy, clock.clone());
         assert!(store
             .insert_with_ttl(1, Item::new(1, 2), Duration::from_secs(1))
             .is_none());
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
         assert!(store.get(&1).is_none());
     }
 
@@ -496,11 +995,12 @@ mod tests {
 
     #[test]
     fn do_not_get_mut_expired_items() {
-        let mut store = Storage::with_capacity(10);
+        let clock = ManualClock::new();
+        let mut store = Storage::with_clock(10, SampledPolicy, clock.clone());
         assert!(store
             .insert_with_ttl(1, Item::new(1, 2), Duration::from_secs(1))
             .is_none());
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
         assert!(store.get_mut(&1).is_none());
     }
 
@@ -607,7 +1107,7 @@ mod tests {
             store.insert(i, Item::new(i, i));
         }
         let admit = TinyLFUCache::new(16);
-        let sample = store.sample(&admit);
+        let sample = store.sample(&admit, &[]);
         assert!(sample.is_some());
     }
 
@@ -615,7 +1115,7 @@ mod tests {
     fn no_sample() {
         let store = Storage::<u64, u64>::with_capacity(10);
         let admit = TinyLFUCache::new(16);
-        let sample = store.sample(&admit);
+        let sample = store.sample(&admit, &[]);
         assert!(sample.is_none());
     }
 
@@ -627,7 +1127,7 @@ mod tests {
         }
         let mut admit = TinyLFUCache::new(16);
         admit.increment(&0);
-        let sample = store.sample(&admit);
+        let sample = store.sample(&admit, &[]);
         assert!(sample.is_some());
         if let Some(sample) = sample {
             assert_eq!(sample.key, 1);
@@ -635,15 +1135,65 @@ mod tests {
     }
 
     #[test]
-    fn cleanup() {
+    fn min_sample_is_always_found() {
         let mut store = Storage::<u64, u64>::with_capacity(10);
+        for i in 0..3 {
+            store.insert(i, Item::new(i, i));
+        }
+        let mut admit = TinyLFUCache::new(16);
+        admit.increment(&0);
+        admit.increment(&2);
+        //items in small storage are sampled without repetition, so the lowest estimate is never missed
+        for _ in 0..100 {
+            assert_eq!(store.sample(&admit, &[]).map(|sample| sample.key), Some(1));
+            assert_eq!(
+                store.sample(&admit, &[1, 2]).map(|sample| sample.key),
+                Some(0)
+            );
+        }
+    }
+
+    #[test]
+    fn exclude_sample() {
+        let mut store = Storage::<u64, u64>::with_capacity(10);
+        for i in 0..3 {
+            store.insert(i, Item::new(i, i));
+        }
+        let admit = TinyLFUCache::new(16);
+        let sample = store.sample(&admit, &[0, 2]);
+        assert!(sample.is_some());
+        if let Some(sample) = sample {
+            assert_eq!(sample.key, 1);
+        }
+        assert!(store.sample(&admit, &[0, 1, 2]).is_none());
+    }
+
+    #[test]
+    fn cost() {
+        let mut store = Storage::<u64, u64>::with_capacity(10);
+        store.insert(1, Item::with_cost(1, 1, 4));
+        store.insert(2, Item::with_cost(2, 2, 3));
+        assert_eq!(store.cost(), 7);
+        assert_eq!(store.room_left(), 3);
+        store.insert(1, Item::with_cost(1, 1, 1));
+        assert_eq!(store.cost(), 4);
+        store.remove(&2);
+        assert_eq!(store.cost(), 1);
+        store.clear();
+        assert_eq!(store.room_left(), 10);
+    }
+
+    #[test]
+    fn cleanup() {
+        let clock = ManualClock::new();
+        let mut store = Storage::<u64, u64, _, _>::with_clock(10, SampledPolicy, clock.clone());
         for i in 0..2 {
             store.insert(i, Item::new(i, i));
         }
         for i in 2..4 {
             store.insert_with_ttl(i, Item::new(i, i), Duration::from_secs(1));
         }
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
         let on_evict = Some(Evict {});
         store.cleanup(&on_evict);
         assert!(store.contains(&0));
@@ -654,19 +1204,54 @@ mod tests {
 
     #[test]
     fn update_with_ttl() {
-        let mut store = Storage::<u64, u64>::with_capacity(10);
+        let clock = ManualClock::new();
+        let mut store = Storage::<u64, u64, _, _>::with_clock(10, SampledPolicy, clock.clone());
         assert!(store
             .insert_with_ttl(1, Item::new(1, 1), Duration::from_secs(2))
             .is_none());
-        std::thread::sleep(Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
         assert!(store.contains(&1));
         assert!(store
             .insert_with_ttl(1, Item::new(1, 1), Duration::from_secs(3))
             .is_some());
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
         store.cleanup::<Evict>(&None);
         assert!(store.contains(&1));
-        std::thread::sleep(Duration::from_secs(2));
+        clock.advance(Duration::from_secs(2));
+        assert!(!store.contains(&1));
+    }
+
+    #[test]
+    fn time_to_idle() {
+        let clock = ManualClock::new();
+        let mut store = Storage::<u64, u64, _, _>::with_clock(10, SampledPolicy, clock.clone());
+        let item = Item::new(1, 1).with_time_to_idle(Duration::from_secs(2));
+        assert!(store.insert(1, item).is_none());
+        for _ in 0..3 {
+            clock.advance(Duration::from_secs(1));
+            assert!(store.get(&1).is_some());
+        }
+        clock.advance(Duration::from_secs(1));
+        assert!(store.get_mut(&1).is_some());
+        clock.advance(Duration::from_secs(3));
+        store.cleanup::<Evict>(&None);
         assert!(!store.contains(&1));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn set_ttl() {
+        let clock = ManualClock::new();
+        let mut store = Storage::<u64, u64, _, _>::with_clock(10, SampledPolicy, clock.clone());
+        assert!(store
+            .insert_with_ttl(1, Item::new(1, 1), Duration::from_secs(1))
+            .is_none());
+        assert!(store.insert(2, Item::new(2, 2)).is_none());
+        store.set_ttl(&1, Duration::from_secs(0));
+        store.set_ttl(&2, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(2));
+        store.cleanup::<Evict>(&None);
+        assert!(store.contains(&1));
+        assert!(!store.contains(&2));
     }
 }
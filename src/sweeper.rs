@@ -0,0 +1,87 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+///
+/// Maintenance thread which periodically runs given task.
+///
+/// Thread is stopped and joined when sweeper is dropped.
+///
+pub struct Sweeper {
+    shutdown: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Sweeper {
+    ///
+    /// Spawn new maintenance thread
+    ///
+    /// # Arguments
+    ///
+    /// - `interval`: how long thread waits between two runs of task
+    /// - `task`: maintenance task
+    ///
+    /// # Panic
+    ///
+    /// If thread can not be spawned.
+    ///
+    pub fn spawn<F>(interval: Duration, mut task: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let (shutdown, signal) = mpsc::channel::<()>();
+        let handle = thread::Builder::new()
+            .name("cascara-sweeper".to_string())
+            .spawn(move || {
+                //any message or dropped sender means shutdown
+                while let Err(RecvTimeoutError::Timeout) = signal.recv_timeout(interval) {
+                    task();
+                }
+            })
+            .expect("spawn sweeper thread");
+        Self {
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Sweeper {
+    fn drop(&mut self) {
+        drop(self.shutdown.take());
+        if let Some(handle) = self.handle.take() {
+            //panic in task is already reported by thread itself
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sweeper::Sweeper;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn run_and_shutdown() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let sweeper = {
+            let runs = runs.clone();
+            Sweeper::spawn(Duration::from_millis(1), move || {
+                runs.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while runs.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+            thread::yield_now();
+        }
+        drop(sweeper);
+        let stopped = runs.load(Ordering::SeqCst);
+        assert!(stopped >= 2);
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(runs.load(Ordering::SeqCst), stopped);
+        assert_eq!(Arc::strong_count(&runs), 1);
+    }
+}
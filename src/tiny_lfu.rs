@@ -1,12 +1,138 @@
+use crate::sketch::FrequencySketch;
 use probabilistic_collections::count_min_sketch::{CountMinSketch, CountMinStrategy};
 use probabilistic_collections::cuckoo::CuckooFilter;
-use std::cmp;
 use std::collections::HashSet;
 
 ///
-/// Max window size before sketcher is reset
+/// Default window size of TinyLFU as multiple of cache capacity
 ///
-pub const MAX_WINDOW_SIZE: usize = 10000;
+pub const WINDOW_SIZE_MULTIPLIER: usize = 10;
+
+///
+/// Accuracy and window size of TinyLFU.
+///
+/// By default count-min sketch over-estimates frequency by at most 10% of window size with 95% confidence,
+/// doorkeeper has 1% false positive rate and window size is 10 times capacity of cache.
+///
+/// # Example
+///
+/// ```
+/// use cascara::{Cache, TinyLFUConfig};
+///
+/// let config = TinyLFUConfig::default()
+///     .with_error(0.01)
+///     .with_confidence(0.99)
+///     .with_doorkeeper_fpr(0.001);
+/// let cache = Cache::<u8, u8>::with_config(100, config);
+/// assert_eq!(config.window_size(100), 1000);
+/// ```
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TinyLFUConfig {
+    error: f64,
+    confidence: f64,
+    doorkeeper_fpr: f64,
+    window_size: Option<usize>,
+}
+
+impl Default for TinyLFUConfig {
+    fn default() -> Self {
+        Self {
+            error: 0.1,
+            confidence: 0.95,
+            doorkeeper_fpr: 0.01,
+            window_size: None,
+        }
+    }
+}
+
+impl TinyLFUConfig {
+    ///
+    /// Set max over-estimation of frequency as fraction of window size
+    ///
+    /// # Panic
+    ///
+    /// If `error` is not in range (0, 1).
+    ///
+    pub fn with_error(mut self, error: f64) -> Self {
+        assert!(error > 0.0 && error < 1.0);
+        self.error = error;
+        self
+    }
+
+    ///
+    /// Set probability that estimated frequency is within error
+    ///
+    /// # Panic
+    ///
+    /// If `confidence` is not in range (0, 1).
+    ///
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        assert!(confidence > 0.0 && confidence < 1.0);
+        self.confidence = confidence;
+        self
+    }
+
+    ///
+    /// Set false positive rate of doorkeeper, which filters out items seen only once in window
+    ///
+    /// # Panic
+    ///
+    /// If `fpr` is not in range (0, 1).
+    ///
+    pub fn with_doorkeeper_fpr(mut self, fpr: f64) -> Self {
+        assert!(fpr > 0.0 && fpr < 1.0);
+        self.doorkeeper_fpr = fpr;
+        self
+    }
+
+    ///
+    /// Set fixed window size instead of multiple of cache capacity
+    ///
+    /// # Panic
+    ///
+    /// If `window_size` is 0.
+    ///
+    pub fn with_window_size(mut self, window_size: usize) -> Self {
+        assert_ne!(window_size, 0);
+        self.window_size = Some(window_size);
+        self
+    }
+
+    ///
+    /// Returns max over-estimation of frequency as fraction of window size
+    ///
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+
+    ///
+    /// Returns probability that estimated frequency is within error
+    ///
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    ///
+    /// Returns false positive rate of doorkeeper
+    ///
+    pub fn doorkeeper_fpr(&self) -> f64 {
+        self.doorkeeper_fpr
+    }
+
+    ///
+    /// Returns window size for cache with given capacity
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    ///
+    pub fn window_size(&self, capacity: usize) -> usize {
+        self.window_size
+            .unwrap_or_else(|| capacity.saturating_mul(WINDOW_SIZE_MULTIPLIER))
+            .max(1)
+    }
+}
 
 pub trait TinyLFU {
     ///
@@ -36,11 +162,27 @@ pub trait TinyLFU {
     /// Clear filter and count-min sketcher.
     ///
     fn clear(&mut self);
+
+    ///
+    /// Returns how many increments can be done, before TinyLFU is reset.
+    ///
+    fn window_size(&self) -> usize;
+
+    ///
+    /// Change how many increments can be done, before TinyLFU is reset.
+    /// By default window size is fixed and change is ignored, so adaptive window has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// - `window_size`: new window size, at least 1
+    ///
+    fn set_window_size(&mut self, _window_size: usize) {}
 }
 
 ///
 /// TinyLFU implementation with application CountMinSketcher and CuckooFilter.
 ///
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TinyLFUCache {
     sketcher: CountMinSketch<CountMinStrategy, u64>,
     filter: CuckooFilter<u64>,
@@ -52,7 +194,7 @@ pub struct TinyLFUCache {
 
 impl TinyLFUCache {
     ///
-    /// Create new instance of TinyLFU with defined windows size.
+    /// Create new instance of TinyLFU with defined windows size and default accuracy.
     ///
     /// # Arguments
     ///
@@ -63,11 +205,25 @@ impl TinyLFUCache {
     /// When `windows_size` == 0
     ///
     pub fn new(window_size: usize) -> Self {
-        assert_ne!(window_size, 0);
-        let window_size = cmp::min(window_size, MAX_WINDOW_SIZE);
+        Self::with_config(
+            window_size,
+            &TinyLFUConfig::default().with_window_size(window_size),
+        )
+    }
+
+    ///
+    /// Create new instance of TinyLFU for cache with given capacity.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `config`: accuracy and window size of TinyLFU
+    ///
+    pub fn with_config(capacity: usize, config: &TinyLFUConfig) -> Self {
+        let window_size = config.window_size(capacity);
         Self {
-            sketcher: CountMinSketch::from_error(0.1, 0.05),
-            filter: CuckooFilter::from_entries_per_index(window_size, 0.01, 8),
+            sketcher: CountMinSketch::from_error(config.error, 1.0 - config.confidence),
+            filter: CuckooFilter::from_entries_per_index(window_size, config.doorkeeper_fpr, 8),
             window_size,
             increments: 0,
             actual_window: HashSet::new(),
@@ -124,11 +280,136 @@ impl TinyLFU for TinyLFUCache {
         self.filter.clear();
         self.increments = 0;
     }
+
+    fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    fn set_window_size(&mut self, window_size: usize) {
+        self.window_size = window_size.max(1);
+    }
+}
+
+///
+/// TinyLFU which does not track frequency of items.
+///
+/// Estimate of every item is 0 and window is never reset.
+/// It fits policies which admit every incoming item, e.g. `LruPolicy`, `LfuPolicy` or `FifoPolicy`.
+///
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VoidTinyLFU;
+
+impl TinyLFU for VoidTinyLFU {
+    fn estimate(&self, _k: &u64) -> i64 {
+        0
+    }
+
+    fn increment(&mut self, _k: &u64) {}
+
+    fn reset(&mut self) {}
+
+    fn clear(&mut self) {}
+
+    fn window_size(&self) -> usize {
+        usize::MAX
+    }
+}
+
+///
+/// Compact TinyLFU implementation with 4-bit counters packed in `u64` words and CuckooFilter as doorkeeper.
+///
+/// Frequencies are aged by halving of all counters, so no set of touched keys is kept.
+/// Estimate of item is at most 16 (15 from sketch + 1 from doorkeeper).
+///
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactTinyLFU {
+    sketch: FrequencySketch,
+    filter: CuckooFilter<u64>,
+    increments: usize,
+    window_size: usize,
+}
+
+impl CompactTinyLFU {
+    ///
+    /// Create new instance of compact TinyLFU with default config.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    ///
+    pub fn new(capacity: usize) -> Self {
+        Self::with_config(capacity, &TinyLFUConfig::default())
+    }
+
+    ///
+    /// Create new instance of compact TinyLFU for cache with given capacity.
+    ///
+    /// Every row of sketch has at least one counter for every item in cache, so cached items rarely share counters.
+    ///
+    /// # Arguments
+    ///
+    /// - `capacity`: max items in cache
+    /// - `config`: accuracy and window size of TinyLFU
+    ///
+    pub fn with_config(capacity: usize, config: &TinyLFUConfig) -> Self {
+        let window_size = config.window_size(capacity);
+        let rows = (1.0 / (1.0 - config.confidence)).ln().ceil() as usize;
+        let width = (1.0_f64.exp() / config.error).ceil() as usize;
+        Self {
+            sketch: FrequencySketch::new(rows.max(1), width.max(capacity)),
+            filter: CuckooFilter::from_entries_per_index(window_size, config.doorkeeper_fpr, 8),
+            increments: 0,
+            window_size,
+        }
+    }
+}
+
+impl TinyLFU for CompactTinyLFU {
+    fn estimate(&self, k: &u64) -> i64 {
+        let mut hits = self.sketch.estimate(k) as i64;
+        if self.filter.contains(k) {
+            hits += 1;
+        }
+        hits
+    }
+
+    fn increment(&mut self, k: &u64) {
+        if self.increments >= self.window_size {
+            self.reset()
+        }
+        if !self.filter.contains(k) {
+            self.filter.insert(k);
+        } else {
+            self.sketch.increment(k);
+        }
+        self.increments += 1;
+    }
+
+    fn reset(&mut self) {
+        self.sketch.halve();
+        self.filter.clear();
+        self.increments = 0;
+    }
+
+    fn clear(&mut self) {
+        self.sketch.clear();
+        self.filter.clear();
+        self.increments = 0;
+    }
+
+    fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    fn set_window_size(&mut self, window_size: usize) {
+        self.window_size = window_size.max(1);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::tiny_lfu::{TinyLFU, TinyLFUCache};
+    use crate::tiny_lfu::{CompactTinyLFU, TinyLFU, TinyLFUCache, TinyLFUConfig};
 
     #[test]
     fn increment() {
@@ -171,4 +452,44 @@ mod tests {
         assert_eq!(tiny.increments, 0);
         assert_eq!(tiny.estimate(&2), 0);
     }
+
+    #[test]
+    fn config() {
+        let config = TinyLFUConfig::default();
+        assert_eq!(config.window_size(1_000_000), 10_000_000);
+        assert_eq!(config.with_window_size(50).window_size(1_000_000), 50);
+
+        let tiny = TinyLFUCache::with_config(100, &config.with_error(0.01).with_confidence(0.99));
+        assert_eq!(tiny.window_size(), 1000);
+        assert_eq!(tiny.sketcher.cols(), 272);
+        assert_eq!(tiny.sketcher.rows(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_config() {
+        let _ = TinyLFUConfig::default().with_error(1.5);
+    }
+
+    #[test]
+    fn compact() {
+        let mut tiny =
+            CompactTinyLFU::with_config(10, &TinyLFUConfig::default().with_window_size(8));
+        assert_eq!(tiny.sketch.estimate(&1), 0);
+        tiny.increment(&1);
+        tiny.increment(&1);
+        tiny.increment(&1);
+        assert_eq!(tiny.estimate(&1), 3);
+        assert_eq!(tiny.estimate(&2), 0);
+        for _ in 0..5 {
+            tiny.increment(&1);
+        }
+        assert_eq!(tiny.estimate(&1), 8);
+        //window is full, so counters are halved and doorkeeper is cleared
+        tiny.increment(&2);
+        assert_eq!(tiny.estimate(&1), 3);
+        assert_eq!(tiny.estimate(&2), 1);
+        tiny.clear();
+        assert_eq!(tiny.estimate(&1), 0);
+    }
 }
@@ -1,20 +1,9 @@
-use std::collections::{BTreeMap, HashSet};
-use std::ops::Add;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 ///
-/// Calculate bucket id as duration in seconds from UNIX epoch
-///
-fn storage_bucket(expiration_time: &SystemTime) -> u64 {
-    expiration_time
-        .duration_since(UNIX_EPOCH)
-        .expect("Unix Epoch")
-        .as_secs()
-}
-
-///
-/// Manage bucket of expiration times for items.
-/// Expiration is defined in seconds.
+/// Manage expiration times of items.
+/// Expiration is defined with millisecond precision.
 ///
 pub trait Expiration {
     ///
@@ -23,14 +12,15 @@ pub trait Expiration {
     /// # Arguments
     ///
     /// - `k`: item identification
-    /// - `expiration`: duration in seconds of item from now
+    /// - `now`: actual time
+    /// - `expiration`: duration of item from now
     ///
     /// # Return
     ///
-    /// If expiration in seconds == 0, than return None.
-    /// Else return system time when item will be expired.
+    /// If expiration is zero, than return None.
+    /// Else return time when item will be expired.
     ///
-    fn insert(&mut self, k: u64, expiration: Duration) -> Option<SystemTime>;
+    fn insert(&mut self, k: u64, now: &Instant, expiration: Duration) -> Option<Instant>;
 
     ///
     /// Update expiration time for given key.
@@ -39,22 +29,24 @@ pub trait Expiration {
     ///
     /// - `k`: item identification
     /// - `expiration_time`: actual expiration time for item.
-    /// - `new_expiration`: duration in seconds of item from now
+    /// - `now`: actual time
+    /// - `new_expiration`: duration of item from now
     ///
     /// # Return
     ///
-    /// If expiration in seconds == 0, than return None.
-    /// Else return system time when item will be expired.
+    /// If expiration is zero, than return None.
+    /// Else return time when item will be expired.
     ///
     fn update(
         &mut self,
         k: u64,
-        expiration_time: &SystemTime,
+        expiration_time: &Instant,
+        now: &Instant,
         new_expiration: Duration,
-    ) -> Option<SystemTime>;
+    ) -> Option<Instant>;
 
     ///
-    /// Remove item expired in given time from buckets.
+    /// Remove item with given expiration time.
     ///
     /// # Arguments
     ///
@@ -65,10 +57,10 @@ pub trait Expiration {
     ///
     /// True if item was removed
     ///
-    fn remove(&mut self, k: &u64, expiration_time: &SystemTime) -> bool;
+    fn remove(&mut self, k: &u64, expiration_time: &Instant) -> bool;
 
     ///
-    /// Remove all item from buckets which are expired at the moment.
+    /// Remove all items which are expired at the moment.
     ///
     /// # Arguments
     ///
@@ -78,7 +70,7 @@ pub trait Expiration {
     ///
     /// HashSet with removed items.
     ///
-    fn cleanup(&mut self, now: &SystemTime) -> HashSet<u64>;
+    fn cleanup(&mut self, now: &Instant) -> HashSet<u64>;
 
     ///
     /// Remove all items
@@ -86,185 +78,311 @@ pub trait Expiration {
     fn clear(&mut self);
 
     ///
-    /// Check if all expiration buckets are empty
+    /// Check if there is no item with expiration time
     ///
     fn is_empty(&self) -> bool;
 }
 
 ///
-/// ExpirationMap holds items in BTreeMap, where key is expiration time in secs from UNIX epoch for given bucket of items.
+/// Number of slots in one level of timer wheel
+///
+const WHEEL_SLOTS: usize = 64;
+
+///
+/// Number of bits of tick which select slot in one level
+///
+const WHEEL_BITS: u32 = 6;
+
+///
+/// Number of levels of timer wheel. Top level covers more than two years in milliseconds,
+/// later expirations are kept in top level too.
+///
+const WHEEL_LEVELS: usize = 6;
+
+///
+/// Hierarchical timer wheel with millisecond ticks.
+///
+/// Every level has 64 slots and every slot of level covers 64 times longer time than slot of lower level.
+/// Item is placed into the lowest level where its expiration tick shares the same slot of higher levels with actual tick.
+/// Items from slots, which were passed during cleanup, are expired or moved into lower levels.
+///
+/// Insert and remove are O(1), cleanup is amortized O(expired items).
 ///
 #[derive(Clone, Debug)]
-pub struct ExpirationMap {
-    buckets: BTreeMap<u64, HashSet<u64>>,
+pub struct TimerWheel {
+    origin: Instant,
+    current: u64,
+    levels: Vec<Vec<HashMap<u64, u64>>>,
+    index: HashMap<u64, (usize, usize)>,
 }
 
-impl ExpirationMap {
+impl TimerWheel {
+    ///
+    /// Create new timer wheel
+    ///
+    /// # Arguments
     ///
-    /// Create new expiration map
+    /// - `origin`: time of the first tick, it can not be later than any expiration time
     ///
-    pub fn new() -> Self {
+    pub fn new(origin: Instant) -> Self {
         Self {
-            buckets: BTreeMap::new(),
+            origin,
+            current: 0,
+            levels: vec![vec![HashMap::new(); WHEEL_SLOTS]; WHEEL_LEVELS],
+            index: HashMap::new(),
         }
     }
+
+    ///
+    /// Returns number of whole milliseconds from origin
+    ///
+    fn tick(&self, time: &Instant) -> u64 {
+        time.saturating_duration_since(self.origin).as_millis() as u64
+    }
+
+    ///
+    /// Returns level and slot for given expiration tick
+    ///
+    fn position(&self, deadline: u64) -> (usize, usize) {
+        let deadline = deadline.max(self.current);
+        let diff = deadline ^ self.current;
+        let level = if diff == 0 {
+            0
+        } else {
+            ((63 - diff.leading_zeros()) / WHEEL_BITS) as usize
+        };
+        let level = level.min(WHEEL_LEVELS - 1);
+        let slot = (deadline >> (WHEEL_BITS * level as u32)) as usize % WHEEL_SLOTS;
+        (level, slot)
+    }
+
+    ///
+    /// Place item into wheel
+    ///
+    fn schedule(&mut self, k: u64, deadline: u64) {
+        let (level, slot) = self.position(deadline);
+        self.levels[level][slot].insert(k, deadline);
+        self.index.insert(k, (level, slot));
+    }
 }
 
-impl Expiration for ExpirationMap {
-    fn insert(&mut self, k: u64, expiration: Duration) -> Option<SystemTime> {
-        if expiration.as_secs() == 0 {
+impl Expiration for TimerWheel {
+    fn insert(&mut self, k: u64, now: &Instant, expiration: Duration) -> Option<Instant> {
+        if expiration.is_zero() {
             return None;
         }
-        let expiration_time = SystemTime::now().add(expiration);
-        let bucket_num = storage_bucket(&expiration_time);
-        if let Some(bucket) = self.buckets.get_mut(&bucket_num) {
-            bucket.insert(k);
-        } else {
-            let mut bucket = HashSet::new();
-            bucket.insert(k);
-            self.buckets.insert(bucket_num, bucket);
-        }
+        let expiration_time = *now + expiration;
+        //item expires in the first tick after its expiration time
+        let deadline = self.tick(&expiration_time) + 1;
+        self.remove(&k, &expiration_time);
+        self.schedule(k, deadline);
         Some(expiration_time)
     }
 
     fn update(
         &mut self,
         k: u64,
-        expiration_time: &SystemTime,
+        expiration_time: &Instant,
+        now: &Instant,
         new_expiration: Duration,
-    ) -> Option<SystemTime> {
+    ) -> Option<Instant> {
         self.remove(&k, expiration_time);
-        self.insert(k, new_expiration)
+        self.insert(k, now, new_expiration)
     }
 
-    fn remove(&mut self, k: &u64, expiration_time: &SystemTime) -> bool {
-        let old_bucket_num = storage_bucket(expiration_time);
-        if let Some(bucket) = self.buckets.get_mut(&old_bucket_num) {
-            bucket.remove(k)
+    fn remove(&mut self, k: &u64, _expiration_time: &Instant) -> bool {
+        if let Some((level, slot)) = self.index.remove(k) {
+            self.levels[level][slot].remove(k);
+            true
         } else {
             false
         }
     }
 
-    fn cleanup(&mut self, now: &SystemTime) -> HashSet<u64> {
-        let now = storage_bucket(now) + 1;
+    fn cleanup(&mut self, now: &Instant) -> HashSet<u64> {
+        let target = self.tick(now);
         let mut result = HashSet::new();
-        let mut buckets = Vec::new();
-        for (id, _) in self.buckets.range(..now) {
-            buckets.push(*id)
+        if target < self.current {
+            return result;
         }
-        for bucket in buckets {
-            for item in self.buckets.remove(&bucket).unwrap() {
-                result.insert(item);
+        let mut pending = Vec::new();
+        for level in 0..WHEEL_LEVELS {
+            let shift = WHEEL_BITS * level as u32;
+            let from = self.current >> shift;
+            let to = target >> shift;
+            //all slots of level are passed
+            let passed = (to - from).min(WHEEL_SLOTS as u64 - 1);
+            for offset in 0..=passed {
+                let slot = ((from + offset) % WHEEL_SLOTS as u64) as usize;
+                if self.levels[level][slot].is_empty() {
+                    continue;
+                }
+                for (k, deadline) in self.levels[level][slot].drain() {
+                    self.index.remove(&k);
+                    if deadline <= target {
+                        result.insert(k);
+                    } else {
+                        pending.push((k, deadline));
+                    }
+                }
             }
         }
+        self.current = target;
+        for (k, deadline) in pending {
+            self.schedule(k, deadline);
+        }
         result
     }
 
     fn clear(&mut self) {
-        self.buckets.clear();
+        for level in self.levels.iter_mut() {
+            for slot in level.iter_mut() {
+                slot.clear();
+            }
+        }
+        self.index.clear();
     }
 
     fn is_empty(&self) -> bool {
-        self.buckets.is_empty()
+        self.index.is_empty()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ttl::{Expiration, ExpirationMap};
-    use std::ops::Add;
-    use std::time::{Duration, SystemTime};
+    use crate::ttl::{Expiration, TimerWheel};
+    use std::time::{Duration, Instant};
 
     #[test]
     fn insert_and_remove() {
-        let mut expiration_map = ExpirationMap::new();
-        let expiration = expiration_map.insert(0, Duration::from_secs(5)).unwrap();
-        assert!(!expiration_map.remove(&1, &expiration));
-        assert!(expiration_map.remove(&0, &expiration));
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        let expiration = wheel.insert(0, &now, Duration::from_secs(5)).unwrap();
+        assert!(!wheel.remove(&1, &expiration));
+        assert!(wheel.remove(&0, &expiration));
+        assert!(wheel.is_empty());
     }
 
     #[test]
     fn cleanup() {
-        let mut expiration_map = ExpirationMap::new();
-        expiration_map.insert(0, Duration::from_secs(1));
-        expiration_map.insert(1, Duration::from_secs(1));
-        expiration_map.insert(2, Duration::from_secs(3));
-        std::thread::sleep(Duration::from_secs(2));
-        let mut removed = expiration_map.cleanup(&SystemTime::now());
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        wheel.insert(0, &now, Duration::from_secs(1));
+        wheel.insert(1, &now, Duration::from_secs(1));
+        wheel.insert(2, &now, Duration::from_secs(3));
+        let mut removed = wheel.cleanup(&(now + Duration::from_secs(2)));
         assert!(removed.remove(&0));
         assert!(removed.remove(&1));
         assert!(removed.is_empty());
-        std::thread::sleep(Duration::from_secs(2));
-        let mut removed = expiration_map.cleanup(&SystemTime::now());
+        let mut removed = wheel.cleanup(&(now + Duration::from_secs(4)));
         assert!(removed.remove(&2));
         assert!(removed.is_empty());
-        assert!(expiration_map.is_empty());
+        assert!(wheel.is_empty());
     }
 
     #[test]
     fn cleanup_unordered() {
-        let mut expiration_map = ExpirationMap::new();
-        expiration_map.insert(0, Duration::from_secs(10));
-        expiration_map.insert(1, Duration::from_secs(1));
-        expiration_map.insert(2, Duration::from_secs(3));
-        std::thread::sleep(Duration::from_secs(2));
-        let mut removed = expiration_map.cleanup(&SystemTime::now());
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        wheel.insert(0, &now, Duration::from_secs(10));
+        wheel.insert(1, &now, Duration::from_secs(1));
+        wheel.insert(2, &now, Duration::from_secs(3));
+        let mut removed = wheel.cleanup(&(now + Duration::from_secs(2)));
         assert!(removed.remove(&1));
         assert!(removed.is_empty());
-        std::thread::sleep(Duration::from_secs(2));
-        let mut removed = expiration_map.cleanup(&SystemTime::now());
+        let mut removed = wheel.cleanup(&(now + Duration::from_secs(4)));
         assert!(removed.remove(&2));
         assert!(removed.is_empty());
-        assert!(!expiration_map.is_empty());
+        assert!(!wheel.is_empty());
     }
 
     #[test]
-    fn clear() {
-        let mut expiration_map = ExpirationMap::new();
-        expiration_map.insert(0, Duration::from_secs(1));
-        expiration_map.insert(1, Duration::from_secs(1));
-        expiration_map.insert(2, Duration::from_secs(3));
-        expiration_map.clear();
-        assert!(expiration_map.is_empty());
+    fn cleanup_milliseconds() {
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        wheel.insert(0, &now, Duration::from_millis(500));
+        wheel.insert(1, &now, Duration::from_millis(1500));
+        assert!(wheel
+            .cleanup(&(now + Duration::from_millis(499)))
+            .is_empty());
+        assert!(wheel
+            .cleanup(&(now + Duration::from_millis(500)))
+            .is_empty());
+        let mut removed = wheel.cleanup(&(now + Duration::from_millis(501)));
+        assert!(removed.remove(&0));
+        assert!(removed.is_empty());
+        let mut removed = wheel.cleanup(&(now + Duration::from_millis(1501)));
+        assert!(removed.remove(&1));
+        assert!(wheel.is_empty());
     }
 
     #[test]
-    fn do_not_register_zero_duration() {
-        let mut expiration_map = ExpirationMap::new();
-        assert!(expiration_map.insert(0, Duration::from_secs(0)).is_none());
+    fn cleanup_cascade() {
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        let expirations = [70, 4_000, 4_200, 300_000, 90_000_000, 100_000_000_000];
+        for (k, expiration) in expirations.iter().enumerate() {
+            wheel.insert(k as u64, &now, Duration::from_millis(*expiration));
+        }
+        let mut time = now;
+        let mut expired = Vec::new();
+        while !wheel.is_empty() {
+            time += Duration::from_millis(997);
+            for k in wheel.cleanup(&time) {
+                let expiration = now + Duration::from_millis(expirations[k as usize]);
+                assert!(time > expiration);
+                if k < 5 {
+                    assert!(time - expiration <= Duration::from_millis(997));
+                }
+                expired.push(k);
+            }
+            if time - now > Duration::from_secs(100_000) {
+                time += Duration::from_millis(100_000_000_000);
+            }
+        }
+        expired.sort();
+        assert_eq!(expired, vec![0, 1, 2, 3, 4, 5]);
     }
 
     #[test]
-    fn remove() {
-        let mut expiration_map = ExpirationMap::new();
-        let expiration_time = expiration_map.insert(0, Duration::from_secs(1));
-        assert!(expiration_time.is_some());
-        if let Some(expiration_time) = expiration_time {
-            assert!(expiration_map.remove(&0, &expiration_time));
-            assert!(!expiration_map.remove(&1, &expiration_time));
-        }
+    fn clear() {
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        wheel.insert(0, &now, Duration::from_secs(1));
+        wheel.insert(1, &now, Duration::from_secs(1));
+        wheel.insert(2, &now, Duration::from_secs(3));
+        wheel.clear();
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn do_not_register_zero_duration() {
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        assert!(wheel.insert(0, &now, Duration::from_secs(0)).is_none());
+        assert!(wheel.insert(0, &now, Duration::from_millis(1)).is_some());
     }
 
     #[test]
     fn remove_not_existing() {
-        let mut expiration_map = ExpirationMap::new();
-        let expiration_time = SystemTime::now().add(Duration::from_secs(10));
-        assert!(!expiration_map.remove(&1, &expiration_time));
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        let expiration_time = now + Duration::from_secs(10);
+        assert!(!wheel.remove(&1, &expiration_time));
     }
 
     #[test]
     fn update() {
-        let mut expiration_map = ExpirationMap::new();
-        let expiration_time = expiration_map.insert(0, Duration::from_secs(1));
-        assert!(expiration_time.is_some());
-        if let Some(expiration_time) = expiration_time {
-            let new_expiration_time =
-                expiration_map.update(0, &expiration_time, Duration::from_secs(1));
-            assert!(new_expiration_time.is_some());
-            if let Some(new_expiration_time) = new_expiration_time {
-                assert!(expiration_map.remove(&0, &new_expiration_time));
-            }
-        }
+        let now = Instant::now();
+        let mut wheel = TimerWheel::new(now);
+        let expiration_time = wheel.insert(0, &now, Duration::from_secs(1)).unwrap();
+        let new_expiration_time = wheel
+            .update(0, &expiration_time, &now, Duration::from_secs(2))
+            .unwrap();
+        assert!(wheel
+            .cleanup(&(now + Duration::from_millis(1500)))
+            .is_empty());
+        assert!(wheel.cleanup(&(now + Duration::from_secs(3))).contains(&0));
+        assert!(!wheel.remove(&0, &new_expiration_time));
     }
 }